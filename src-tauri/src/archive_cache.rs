@@ -0,0 +1,319 @@
+use crate::utils::{self, Localization};
+use anyhow::Context;
+use log::{debug, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::Manager;
+
+pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Downloaded localization archives shared by every game directory.
+/// Entries are named by content hash when the source provides one,
+/// otherwise by id, version and size. The modification time of an
+/// entry is bumped on every use and the least recently used entries
+/// are evicted once the cache grows past `max_size`.
+pub struct ArchiveCache {
+    dir: PathBuf,
+    max_size: u64, // 0 disables caching
+}
+
+impl ArchiveCache {
+    pub fn new(app_handle: &tauri::AppHandle, max_size: u64) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            dir: app_handle.path().app_cache_dir()?.join("Archives"),
+            max_size,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_size > 0
+    }
+
+    fn get_entry_path(&self, localization: &Localization) -> PathBuf {
+        let key = match &localization.hash {
+            Some(hash) => hash.to_lowercase(),
+            None => format!(
+                "{}-{}-{}",
+                localization.id, localization.version, localization.size
+            ),
+        };
+
+        // Ids and versions come from remote sources, keep the name a plain file name
+        let key: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+
+        self.dir.join(format!("{}.zip", key))
+    }
+
+    /// Returns the cached archive of `localization` if it is present and intact.
+    pub fn get(&self, localization: &Localization) -> Option<PathBuf> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let entry_path = self.get_entry_path(localization);
+        if !entry_path.is_file() {
+            return None;
+        }
+
+        if let Err(e) = verify_archive(&entry_path, localization) {
+            warn!("Discarding cached archive {:?}: {}", entry_path, e);
+            fs::remove_file(&entry_path).ok();
+            return None;
+        }
+
+        touch(&entry_path);
+        debug!("Using cached archive {:?}", entry_path);
+        Some(entry_path)
+    }
+
+    /// Moves a verified download into the cache and returns its new location.
+    pub fn insert(
+        &self,
+        localization: &Localization,
+        download_path: &Path,
+    ) -> Result<PathBuf, anyhow::Error> {
+        let entry_path = self.get_entry_path(localization);
+
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create archive cache {:?}", self.dir))?;
+
+        self.evict(localization.size, &entry_path)?;
+
+        // Another install may have cached the same archive in the meantime
+        if entry_path.is_file() {
+            touch(&entry_path);
+            return Ok(entry_path);
+        }
+
+        if fs::rename(download_path, &entry_path).is_err() {
+            fs::copy(download_path, &entry_path).with_context(|| {
+                format!(
+                    "Failed to copy archive {:?} to {:?}",
+                    download_path, entry_path
+                )
+            })?;
+        }
+        touch(&entry_path);
+
+        info!("Cached archive {:?}", entry_path);
+        Ok(entry_path)
+    }
+
+    /// Removes least recently used entries until `incoming_size` more bytes fit.
+    fn evict(&self, incoming_size: u64, keep: &Path) -> Result<(), anyhow::Error> {
+        let mut entries = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read archive cache {:?}", self.dir))?
+        {
+            let entry = entry.with_context(|| format!("Error reading entry in {:?}", self.dir))?;
+            let path = entry.path();
+
+            if !path.is_file() || path == keep {
+                continue;
+            }
+
+            let metadata = entry
+                .metadata()
+                .with_context(|| format!("Failed to get metadata of {:?}", path))?;
+            let last_used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((last_used, metadata.len(), path));
+        }
+
+        let mut total_size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(last_used, _, _)| *last_used);
+
+        for (_, size, path) in entries {
+            if total_size + incoming_size <= self.max_size {
+                break;
+            }
+
+            match fs::remove_file(&path) {
+                Ok(()) => {
+                    debug!("Evicted cached archive {:?}", path);
+                    total_size -= size;
+                }
+                Err(e) => warn!("Failed to evict cached archive {:?}: {}", path, e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn verify_archive(path: &Path, localization: &Localization) -> Result<(), anyhow::Error> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("Failed to get file size of {:?}", path))?
+        .len();
+
+    if size != localization.size {
+        return Err(anyhow::anyhow!(
+            "File size mismatch (expected: {}, found: {})",
+            localization.size,
+            size
+        ));
+    }
+
+    if let Some(expected_hash) = &localization.hash {
+        let calculated_hash = utils::calculate_md5(path)?;
+        if !calculated_hash.eq_ignore_ascii_case(expected_hash) {
+            return Err(anyhow::anyhow!(
+                "Archive hash mismatch (expected: {}, found: {})",
+                expected_hash,
+                calculated_hash
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn touch(path: &Path) {
+    let result = fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+
+    if let Err(e) = result {
+        warn!("Failed to update last use of {:?}: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::test_localization;
+    use std::time::Duration;
+
+    fn localization(version: &str, content: &[u8]) -> Localization {
+        Localization {
+            size: content.len() as u64,
+            ..test_localization(version, &[])
+        }
+    }
+
+    fn download(dir: &Path, name: &str, content: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn set_last_used(path: &Path, seconds_ago: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(seconds_ago))
+            .unwrap();
+    }
+
+    #[test]
+    fn cache_stays_under_the_size_cap() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let download_dir = tempfile::tempdir().unwrap();
+        let cache = ArchiveCache {
+            dir: cache_dir.path().join("Archives"),
+            max_size: 100,
+        };
+
+        let old = localization("1.0", &[1; 60]);
+        let old_path = cache
+            .insert(&old, &download(download_dir.path(), "old.zip", &[1; 60]))
+            .unwrap();
+        set_last_used(&old_path, 60);
+
+        let new = localization("2.0", &[2; 60]);
+        let new_path = cache
+            .insert(&new, &download(download_dir.path(), "new.zip", &[2; 60]))
+            .unwrap();
+
+        assert!(!old_path.exists());
+        assert_eq!(fs::read(&new_path).unwrap(), [2; 60]);
+        assert!(cache.get(&old).is_none());
+        assert_eq!(cache.get(&new), Some(new_path));
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_first() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let download_dir = tempfile::tempdir().unwrap();
+        let cache = ArchiveCache {
+            dir: cache_dir.path().join("Archives"),
+            max_size: 100,
+        };
+
+        let versions = ["1.0", "2.0", "3.0"];
+        for (age, version) in versions.iter().enumerate() {
+            let path = cache
+                .insert(
+                    &localization(version, &[0; 30]),
+                    &download(download_dir.path(), version, &[0; 30]),
+                )
+                .unwrap();
+            set_last_used(&path, 60 * (3 - age as u64));
+        }
+
+        // Using the oldest entry makes 2.0 the least recently used one
+        assert!(cache.get(&localization("1.0", &[0; 30])).is_some());
+
+        cache
+            .insert(
+                &localization("4.0", &[0; 30]),
+                &download(download_dir.path(), "4.0", &[0; 30]),
+            )
+            .unwrap();
+
+        for (version, cached) in [("1.0", true), ("2.0", false), ("3.0", true), ("4.0", true)] {
+            assert_eq!(
+                cache.get(&localization(version, &[0; 30])).is_some(),
+                cached,
+                "{}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn verify_archive_rejects_hash_mismatch() {
+        let download_dir = tempfile::tempdir().unwrap();
+        let path = download(download_dir.path(), "ru.zip", b"archive");
+
+        let mut localization = localization("1.0", b"archive");
+        localization.hash = Some(utils::calculate_md5(&path).unwrap().to_uppercase());
+        verify_archive(&path, &localization).unwrap();
+
+        localization.hash = Some("0123456789abcdef0123456789abcdef".to_string());
+        let error = verify_archive(&path, &localization).unwrap_err();
+        assert!(error.to_string().contains("hash mismatch"));
+    }
+
+    #[test]
+    fn corrupt_entries_are_discarded() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let download_dir = tempfile::tempdir().unwrap();
+        let cache = ArchiveCache {
+            dir: cache_dir.path().join("Archives"),
+            max_size: 100,
+        };
+
+        let download_path = download(download_dir.path(), "ru.zip", b"archive");
+        let mut localization = localization("1.0", b"archive");
+        localization.hash = Some(utils::calculate_md5(&download_path).unwrap());
+        let entry_path = cache.insert(&localization, &download_path).unwrap();
+
+        fs::write(&entry_path, b"corrupt").unwrap();
+
+        assert!(cache.get(&localization).is_none());
+        assert!(!entry_path.exists());
+    }
+}
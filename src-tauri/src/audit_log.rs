@@ -0,0 +1,345 @@
+use anyhow::Context;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const AUDIT_LOG_FILE_NAME: &str = "history.jsonl";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Install,
+    Update,
+    Uninstall,
+    Repair,
+    Rollback, // Reinstalling an older version, recorded once rollback is supported
+}
+
+/// One finished operation on a localization.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEvent {
+    pub timestamp: u64, // Unix timestamp of the start
+    pub action: AuditAction,
+    pub profile: Option<String>,
+    pub localization: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    pub source: Option<String>,
+    pub game_build: Option<u64>,
+    pub duration_ms: u64,
+    pub error: Option<String>, // None if the operation succeeded
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, profile: Option<&str>, localization: &str) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            action,
+            profile: profile.map(|profile| profile.to_string()),
+            localization: localization.to_string(),
+            old_version: None,
+            new_version: None,
+            source: None,
+            game_build: None,
+            duration_ms: 0,
+            error: None,
+        }
+    }
+
+    pub fn finish(&mut self, started: Instant, result: &Result<(), String>) {
+        self.duration_ms = started.elapsed().as_millis() as u64;
+        self.error = result.as_ref().err().cloned();
+    }
+}
+
+/// Criteria for `query_events`, every field is optional.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub localization: Option<String>,
+    pub profile: Option<String>,
+    pub source: Option<String>,
+    pub actions: Vec<AuditAction>, // Empty matches every action
+    pub succeeded: Option<bool>,
+    pub since: Option<u64>, // Unix timestamp, inclusive
+    pub until: Option<u64>, // Unix timestamp, exclusive
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, event: &AuditEvent) -> bool {
+        self.localization
+            .as_ref()
+            .is_none_or(|localization| *localization == event.localization)
+            && self
+                .profile
+                .as_ref()
+                .is_none_or(|profile| event.profile.as_ref() == Some(profile))
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|source| event.source.as_ref() == Some(source))
+            && (self.actions.is_empty() || self.actions.contains(&event.action))
+            && self
+                .succeeded
+                .is_none_or(|succeeded| succeeded == event.error.is_none())
+            && self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp < until)
+    }
+}
+
+fn get_audit_log_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, anyhow::Error> {
+    Ok(app_handle.path().app_data_dir()?.join(AUDIT_LOG_FILE_NAME))
+}
+
+/// Appends `event` as one JSON line. Existing entries are never rewritten.
+pub fn append_event(
+    app_handle: &tauri::AppHandle,
+    event: &AuditEvent,
+) -> Result<(), anyhow::Error> {
+    append_event_to(&get_audit_log_path(app_handle)?, event)
+}
+
+fn append_event_to(audit_log_path: &Path, event: &AuditEvent) -> Result<(), anyhow::Error> {
+    if let Some(parent_dir) = audit_log_path.parent() {
+        fs::create_dir_all(parent_dir)?;
+    }
+
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(audit_log_path)
+        .with_context(|| format!("Failed to open audit log {:?}", audit_log_path))?;
+
+    // Don't glue the new event to a line cut short by a crash
+    let file_size = file.metadata()?.len();
+    if file_size > 0 {
+        let mut last_byte = [0; 1];
+        file.seek(SeekFrom::Start(file_size - 1))?;
+        file.read_exact(&mut last_byte)?;
+        if last_byte[0] != b'\n' {
+            line.insert(0, '\n');
+        }
+    }
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write audit log {:?}", audit_log_path))?;
+    file.sync_data()?;
+
+    Ok(())
+}
+
+/// Returns the events matching `filter`, newest first.
+pub fn query_events(
+    app_handle: &tauri::AppHandle,
+    filter: &AuditFilter,
+) -> Result<Vec<AuditEvent>, anyhow::Error> {
+    read_events(&get_audit_log_path(app_handle)?, filter)
+}
+
+fn read_events(
+    audit_log_path: &Path,
+    filter: &AuditFilter,
+) -> Result<Vec<AuditEvent>, anyhow::Error> {
+    if !audit_log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let audit_log_content = fs::read_to_string(audit_log_path)
+        .with_context(|| format!("Failed to read audit log {:?}", audit_log_path))?;
+
+    let lines: Vec<&str> = audit_log_content.lines().collect();
+
+    let mut events = Vec::new();
+    for (index, line) in lines.into_iter().enumerate().rev() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // A crash can leave the last line half written
+        let event: AuditEvent = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Skipping malformed audit log line {}: {}", index + 1, e);
+                continue;
+            }
+        };
+
+        if !filter.matches(&event) {
+            continue;
+        }
+
+        events.push(event);
+        if filter.limit.is_some_and(|limit| events.len() >= limit) {
+            break;
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(
+        timestamp: u64,
+        action: AuditAction,
+        localization: &str,
+        error: Option<&str>,
+    ) -> AuditEvent {
+        AuditEvent {
+            timestamp,
+            profile: Some("Default".to_string()),
+            source: Some("github".to_string()),
+            error: error.map(|error| error.to_string()),
+            ..AuditEvent::new(action, None, localization)
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = AuditFilter::default();
+
+        assert!(filter.matches(&event(1, AuditAction::Install, "ru", None)));
+        assert!(filter.matches(&event(1, AuditAction::Rollback, "en", Some("failed"))));
+    }
+
+    #[test]
+    fn filter_matches_each_field() {
+        let installed = event(100, AuditAction::Install, "ru", None);
+        let failed = event(200, AuditAction::Update, "ru", Some("HTTP error 404"));
+
+        let by_localization = AuditFilter {
+            localization: Some("en".to_string()),
+            ..Default::default()
+        };
+        assert!(!by_localization.matches(&installed));
+
+        let by_profile = AuditFilter {
+            profile: Some("Steam Deck".to_string()),
+            ..Default::default()
+        };
+        assert!(!by_profile.matches(&installed));
+        assert!(!by_profile.matches(&AuditEvent::new(AuditAction::Install, None, "ru")));
+
+        let by_source = AuditFilter {
+            source: Some("github".to_string()),
+            ..Default::default()
+        };
+        assert!(by_source.matches(&installed));
+        assert!(!by_source.matches(&AuditEvent::new(AuditAction::Install, None, "ru")));
+
+        let by_action = AuditFilter {
+            actions: vec![AuditAction::Update, AuditAction::Uninstall],
+            ..Default::default()
+        };
+        assert!(!by_action.matches(&installed));
+        assert!(by_action.matches(&failed));
+
+        let succeeded = AuditFilter {
+            succeeded: Some(true),
+            ..Default::default()
+        };
+        assert!(succeeded.matches(&installed));
+        assert!(!succeeded.matches(&failed));
+
+        let in_range = AuditFilter {
+            since: Some(100),
+            until: Some(200),
+            ..Default::default()
+        };
+        assert!(in_range.matches(&installed));
+        assert!(!in_range.matches(&failed));
+    }
+
+    #[test]
+    fn queries_newest_first_with_limit() {
+        let log_dir = tempfile::tempdir().unwrap();
+        let audit_log_path = log_dir.path().join(AUDIT_LOG_FILE_NAME);
+
+        for (timestamp, localization) in [(1, "ru"), (2, "en"), (3, "ru")] {
+            append_event_to(
+                &audit_log_path,
+                &event(timestamp, AuditAction::Install, localization, None),
+            )
+            .unwrap();
+        }
+
+        let filter = AuditFilter {
+            localization: Some("ru".to_string()),
+            ..Default::default()
+        };
+        let timestamps: Vec<u64> = read_events(&audit_log_path, &filter)
+            .unwrap()
+            .iter()
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![3, 1]);
+
+        let filter = AuditFilter {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let timestamps: Vec<u64> = read_events(&audit_log_path, &filter)
+            .unwrap()
+            .iter()
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![3, 2]);
+    }
+
+    #[test]
+    fn skips_truncated_last_line() {
+        let log_dir = tempfile::tempdir().unwrap();
+        let audit_log_path = log_dir.path().join(AUDIT_LOG_FILE_NAME);
+
+        append_event_to(&audit_log_path, &event(1, AuditAction::Install, "ru", None)).unwrap();
+        let line = serde_json::to_string(&event(2, AuditAction::Update, "ru", None)).unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&audit_log_path)
+            .unwrap();
+        file.write_all(&line.as_bytes()[..line.len() / 2]).unwrap();
+        drop(file);
+
+        let events = read_events(&audit_log_path, &AuditFilter::default()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].timestamp, 1);
+
+        // The next event starts on its own line instead of extending the broken one
+        append_event_to(
+            &audit_log_path,
+            &event(3, AuditAction::Uninstall, "ru", None),
+        )
+        .unwrap();
+        let timestamps: Vec<u64> = read_events(&audit_log_path, &AuditFilter::default())
+            .unwrap()
+            .iter()
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![3, 1]);
+    }
+
+    #[test]
+    fn missing_log_has_no_events() {
+        let log_dir = tempfile::tempdir().unwrap();
+
+        let events = read_events(
+            &log_dir.path().join(AUDIT_LOG_FILE_NAME),
+            &AuditFilter::default(),
+        )
+        .unwrap();
+        assert!(events.is_empty());
+    }
+}
@@ -0,0 +1,546 @@
+use crate::settings::{AppSettings, LocalizationSource};
+use crate::utils::{self, InstalledMetadata, Localization};
+use anyhow::Context;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BundledLocalization {
+    pub id: String,
+    pub version: String,
+    pub source: String,
+}
+
+/// Settings and installed localizations of a profile, for moving to another PC.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SettingsBundle {
+    pub format_version: u32,
+    pub exported_at: u64, // Unix timestamp
+    pub sources: HashMap<String, LocalizationSource>,
+    pub selected_source: Option<String>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub installed: Vec<BundledLocalization>,
+}
+
+/// What to do with a bundled source whose key is taken by a different URL.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    Keep,      // Keep the existing source
+    Overwrite, // Replace the existing source and selected source/language
+    Rename,    // Import the bundled source under a new key
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ImportReport {
+    pub added_sources: Vec<String>,
+    pub updated_sources: Vec<String>,
+    pub kept_sources: Vec<String>,
+    pub renamed_sources: Vec<(String, String)>, // Bundled key, new key
+    pub rejected_sources: Vec<(String, String)>, // Bundled key, why its url is invalid
+    pub queued: Vec<String>, // Queued for installation in the source's latest version
+    pub up_to_date: Vec<String>, // Already installed in the source's latest version
+    pub missing: Vec<String>, // Not offered by their source anymore
+}
+
+impl SettingsBundle {
+    pub fn new(settings: &AppSettings, installed_metadata: Option<&InstalledMetadata>) -> Self {
+        let mut installed: Vec<BundledLocalization> = installed_metadata
+            .map(|metadata| {
+                metadata
+                    .installed
+                    .values()
+                    .map(|installed| BundledLocalization {
+                        id: installed.id.clone(),
+                        version: installed.version.clone(),
+                        source: installed.source.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        installed.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            sources: settings.sources.clone(),
+            selected_source: settings.selected_source.clone(),
+            language: settings.language.clone(),
+            installed,
+        }
+    }
+
+    /// Merges the bundled sources, selected source and language into `settings`.
+    /// Returns the key each bundled source ended up under, sources with an
+    /// invalid url are left out and reported in `rejected_sources`.
+    pub fn apply_settings(
+        &self,
+        settings: &mut AppSettings,
+        policy: ConflictPolicy,
+        report: &mut ImportReport,
+    ) -> HashMap<String, String> {
+        let mut source_keys = HashMap::new();
+
+        let mut bundled_keys: Vec<&String> = self.sources.keys().collect();
+        bundled_keys.sort();
+
+        for key in bundled_keys {
+            let source = &self.sources[key];
+
+            // Bundles are shared files, don't take their urls on trust
+            if let Err(e) = utils::validate_source_url(&source.url) {
+                warn!("Rejecting bundled source '{}': {}", key, e);
+                report.rejected_sources.push((key.clone(), e.to_string()));
+                continue;
+            }
+
+            // The same URL under another key is the same source
+            if let Some((existing_key, _)) = settings
+                .sources
+                .iter()
+                .find(|(_, existing)| existing.url == source.url)
+            {
+                source_keys.insert(key.clone(), existing_key.clone());
+                continue;
+            }
+
+            if !settings.sources.contains_key(key) {
+                settings.sources.insert(key.clone(), source.clone());
+                report.added_sources.push(key.clone());
+                source_keys.insert(key.clone(), key.clone());
+                continue;
+            }
+
+            match policy {
+                ConflictPolicy::Keep => {
+                    report.kept_sources.push(key.clone());
+                    source_keys.insert(key.clone(), key.clone());
+                }
+                ConflictPolicy::Overwrite => {
+                    settings.sources.insert(key.clone(), source.clone());
+                    report.updated_sources.push(key.clone());
+                    source_keys.insert(key.clone(), key.clone());
+                }
+                ConflictPolicy::Rename => {
+                    let new_key = (2..)
+                        .map(|n| format!("{}-{}", key, n))
+                        .find(|new_key| !settings.sources.contains_key(new_key))
+                        .unwrap();
+                    settings.sources.insert(new_key.clone(), source.clone());
+                    report.renamed_sources.push((key.clone(), new_key.clone()));
+                    source_keys.insert(key.clone(), new_key);
+                }
+            }
+        }
+
+        let overwrite = policy == ConflictPolicy::Overwrite;
+
+        if let Some(selected_source) = self
+            .selected_source
+            .as_ref()
+            .and_then(|selected_source| source_keys.get(selected_source))
+        {
+            if overwrite || settings.selected_source.is_none() {
+                settings.selected_source = Some(selected_source.clone());
+            }
+        }
+
+        if self.language.is_some() && (overwrite || settings.language.is_none()) {
+            settings.language = self.language.clone();
+        }
+
+        source_keys
+    }
+
+    /// Returns the key a bundled localization's source ended up under,
+    /// None if that source was rejected.
+    fn get_source_key(
+        &self,
+        bundled: &BundledLocalization,
+        source_keys: &HashMap<String, String>,
+    ) -> Option<String> {
+        match source_keys.get(&bundled.source) {
+            Some(source_key) => Some(source_key.clone()),
+            None if self.sources.contains_key(&bundled.source) => None,
+            None => Some(bundled.source.clone()), // Not bundled, may be configured already
+        }
+    }
+
+    /// Keys of the sources the bundled localizations have to be looked up in.
+    pub fn get_installed_source_keys(&self, source_keys: &HashMap<String, String>) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .installed
+            .iter()
+            .filter_map(|bundled| self.get_source_key(bundled, source_keys))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Sorts the bundled localizations into `report.queued`, `report.up_to_date`
+    /// and `report.missing`, and returns the source key and latest version of
+    /// each one to queue. `manifests` holds the localizations of every source
+    /// that could be fetched.
+    pub fn plan_installs(
+        &self,
+        source_keys: &HashMap<String, String>,
+        manifests: &HashMap<String, Vec<Localization>>,
+        installed_metadata: &InstalledMetadata,
+        report: &mut ImportReport,
+    ) -> Vec<(String, Localization)> {
+        let mut installs = Vec::new();
+
+        for bundled in &self.installed {
+            let Some(source_key) = self.get_source_key(bundled, source_keys) else {
+                warn!(
+                    "Source '{}' of '{}' was rejected",
+                    bundled.source, bundled.id
+                );
+                report.missing.push(bundled.id.clone());
+                continue;
+            };
+
+            let Some(localization) = manifests
+                .get(&source_key)
+                .into_iter()
+                .flatten()
+                .find(|localization| localization.id == bundled.id)
+            else {
+                warn!("Source '{}' doesn't offer '{}'", source_key, bundled.id);
+                report.missing.push(bundled.id.clone());
+                continue;
+            };
+
+            let is_up_to_date = installed_metadata
+                .installed
+                .get(&bundled.id)
+                .is_some_and(|installed| installed.version == localization.version);
+
+            if is_up_to_date {
+                report.up_to_date.push(bundled.id.clone());
+                continue;
+            }
+
+            report.queued.push(bundled.id.clone());
+            installs.push((source_key, localization.clone()));
+        }
+
+        installs
+    }
+}
+
+pub fn export_bundle(path: &Path, bundle: &SettingsBundle) -> Result<(), anyhow::Error> {
+    let bundle_content = toml::to_string(bundle)?;
+    fs::write(path, bundle_content)
+        .with_context(|| format!("Failed to write settings bundle {:?}", path))?;
+    Ok(())
+}
+
+pub fn load_bundle(path: &Path) -> Result<SettingsBundle, anyhow::Error> {
+    let bundle_content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read settings bundle {:?}", path))?;
+    let bundle: SettingsBundle = toml::from_str(&bundle_content)
+        .with_context(|| format!("Failed to parse settings bundle {:?}", path))?;
+
+    if bundle.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Settings bundle version {} is not supported",
+            bundle.format_version
+        ));
+    }
+
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::test_localization;
+
+    fn source(url: &str) -> LocalizationSource {
+        LocalizationSource {
+            name: url.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    fn bundled(id: &str, source: &str) -> BundledLocalization {
+        BundledLocalization {
+            id: id.to_string(),
+            version: "1.0".to_string(),
+            source: source.to_string(),
+        }
+    }
+
+    fn localization(id: &str, version: &str) -> Localization {
+        Localization {
+            id: id.to_string(),
+            ..test_localization(version, &[])
+        }
+    }
+
+    fn bundle_with_sources(sources: &[(&str, &str)]) -> SettingsBundle {
+        SettingsBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: 0,
+            sources: sources
+                .iter()
+                .map(|(key, url)| {
+                    (
+                        key.to_string(),
+                        LocalizationSource {
+                            name: key.to_string(),
+                            url: url.to_string(),
+                        },
+                    )
+                })
+                .collect(),
+            selected_source: Some("local".to_string()),
+            language: None,
+            installed: vec![],
+        }
+    }
+
+    #[test]
+    fn rejects_bundled_sources_with_invalid_urls() {
+        let bundle = bundle_with_sources(&[
+            ("github", "https://example.com/localizations.json"),
+            ("local", "file:///etc/passwd"),
+            ("script", "javascript:alert(1)"),
+            ("broken", "not a url"),
+        ]);
+        let mut settings = AppSettings::default();
+        let mut report = ImportReport::default();
+
+        let source_keys =
+            bundle.apply_settings(&mut settings, ConflictPolicy::Overwrite, &mut report);
+
+        assert_eq!(report.added_sources, vec!["github"]);
+        let rejected: Vec<&str> = report
+            .rejected_sources
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect();
+        assert_eq!(rejected, vec!["broken", "local", "script"]);
+
+        assert_eq!(settings.sources.len(), 1);
+        assert_eq!(source_keys.len(), 1);
+        assert_eq!(settings.selected_source, None);
+    }
+
+    #[test]
+    fn keeps_selected_source_when_bundled_one_is_rejected() {
+        let bundle = bundle_with_sources(&[("local", "file:///etc/passwd")]);
+        let mut settings = AppSettings {
+            selected_source: Some("github".to_string()),
+            ..Default::default()
+        };
+        let mut report = ImportReport::default();
+
+        bundle.apply_settings(&mut settings, ConflictPolicy::Overwrite, &mut report);
+
+        assert_eq!(settings.selected_source.as_deref(), Some("github"));
+    }
+
+    #[test]
+    fn applies_each_conflict_policy() {
+        const EXISTING: &str = "https://example.com/existing.json";
+        const BUNDLED: &str = "https://example.com/bundled.json";
+
+        // Policy, url of "github", key the bundled source maps to and its url,
+        // selected source, language
+        let cases = [
+            (
+                ConflictPolicy::Keep,
+                EXISTING,
+                "github",
+                EXISTING,
+                "other",
+                "en",
+            ),
+            (
+                ConflictPolicy::Overwrite,
+                BUNDLED,
+                "github",
+                BUNDLED,
+                "github",
+                "ru-RU",
+            ),
+            (
+                ConflictPolicy::Rename,
+                EXISTING,
+                "github-3",
+                BUNDLED,
+                "other",
+                "en",
+            ),
+        ];
+
+        for (policy, github_url, bundled_key, bundled_url, selected_source, language) in cases {
+            let mut bundle = bundle_with_sources(&[("github", BUNDLED)]);
+            bundle.selected_source = Some("github".to_string());
+            bundle.language = Some("ru-RU".to_string());
+
+            let mut settings = AppSettings {
+                sources: HashMap::from([
+                    ("github".to_string(), source(EXISTING)),
+                    (
+                        "github-2".to_string(),
+                        source("https://example.com/other.json"),
+                    ),
+                ]),
+                selected_source: Some("other".to_string()),
+                language: Some("en".to_string()),
+                ..Default::default()
+            };
+            let mut report = ImportReport::default();
+
+            let source_keys = bundle.apply_settings(&mut settings, policy, &mut report);
+
+            assert_eq!(settings.sources["github"].url, github_url, "{:?}", policy);
+            assert_eq!(
+                settings.sources[bundled_key].url, bundled_url,
+                "{:?}",
+                policy
+            );
+            assert_eq!(source_keys["github"], bundled_key, "{:?}", policy);
+            assert_eq!(
+                settings.selected_source.as_deref(),
+                Some(selected_source),
+                "{:?}",
+                policy
+            );
+            assert_eq!(settings.language.as_deref(), Some(language), "{:?}", policy);
+
+            let kept = policy == ConflictPolicy::Keep;
+            let updated = policy == ConflictPolicy::Overwrite;
+            assert_eq!(report.kept_sources.len(), kept as usize, "{:?}", policy);
+            assert_eq!(
+                report.updated_sources.len(),
+                updated as usize,
+                "{:?}",
+                policy
+            );
+            assert!(report.added_sources.is_empty());
+        }
+    }
+
+    #[test]
+    fn renamed_sources_skip_taken_keys() {
+        let bundle = bundle_with_sources(&[
+            ("github", "https://example.com/bundled.json"),
+            ("github-2", "https://example.com/bundled-2.json"),
+        ]);
+        let mut settings = AppSettings {
+            sources: HashMap::from([
+                ("github".to_string(), source("https://example.com/a.json")),
+                ("github-2".to_string(), source("https://example.com/b.json")),
+                ("github-3".to_string(), source("https://example.com/c.json")),
+            ]),
+            ..Default::default()
+        };
+        let mut report = ImportReport::default();
+
+        bundle.apply_settings(&mut settings, ConflictPolicy::Rename, &mut report);
+
+        assert_eq!(
+            report.renamed_sources,
+            vec![
+                ("github".to_string(), "github-4".to_string()),
+                ("github-2".to_string(), "github-2-2".to_string()),
+            ]
+        );
+        assert_eq!(settings.sources.len(), 5);
+    }
+
+    #[test]
+    fn same_url_under_another_key_is_reused() {
+        let bundle = bundle_with_sources(&[("mirror", "https://example.com/localizations.json")]);
+        let mut settings = AppSettings {
+            sources: HashMap::from([(
+                "github".to_string(),
+                source("https://example.com/localizations.json"),
+            )]),
+            ..Default::default()
+        };
+        let mut report = ImportReport::default();
+
+        let source_keys = bundle.apply_settings(&mut settings, ConflictPolicy::Rename, &mut report);
+
+        assert_eq!(source_keys["mirror"], "github");
+        assert_eq!(settings.sources.len(), 1);
+        assert!(report.renamed_sources.is_empty());
+    }
+
+    #[test]
+    fn classifies_bundled_localizations() {
+        let mut bundle = bundle_with_sources(&[("local", "file:///etc/passwd")]);
+        bundle.installed = vec![
+            bundled("current", "github"),
+            bundled("outdated", "github"),
+            bundled("removed", "github"),
+            bundled("rejected", "local"),
+            bundled("unconfigured", "unknown"),
+            bundled("renamed", "mirror"),
+        ];
+        let source_keys = HashMap::from([("mirror".to_string(), "mirror-2".to_string())]);
+
+        assert_eq!(
+            bundle.get_installed_source_keys(&source_keys),
+            vec!["github", "mirror-2", "unknown"]
+        );
+
+        // "unknown" isn't configured, so it has no manifest
+        let manifests = HashMap::from([
+            (
+                "github".to_string(),
+                vec![
+                    localization("current", "2.0"),
+                    localization("outdated", "2.0"),
+                ],
+            ),
+            ("mirror-2".to_string(), vec![localization("renamed", "1.0")]),
+        ]);
+
+        let mut installed_metadata = InstalledMetadata::new();
+        installed_metadata.add_localization(&localization("current", "2.0"), "github", None);
+        installed_metadata.add_localization(&localization("outdated", "1.0"), "github", None);
+
+        let mut report = ImportReport::default();
+        let installs =
+            bundle.plan_installs(&source_keys, &manifests, &installed_metadata, &mut report);
+
+        assert_eq!(report.up_to_date, vec!["current"]);
+        assert_eq!(report.queued, vec!["outdated", "renamed"]);
+        assert_eq!(report.missing, vec!["removed", "rejected", "unconfigured"]);
+
+        let installs: Vec<(&str, &str, &str)> = installs
+            .iter()
+            .map(|(source_key, localization)| {
+                (
+                    source_key.as_str(),
+                    localization.id.as_str(),
+                    localization.version.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            installs,
+            vec![
+                ("github", "outdated", "2.0"),
+                ("mirror-2", "renamed", "1.0")
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::deep_link;
+use log::warn;
+
+/// Actions requested on the command line, e.g. by a desktop shortcut:
+/// `--source <name> --install <id> --update-and-play`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CliArgs {
+    pub source: Option<String>, // Source key or name to select
+    pub install: Vec<String>,   // Localization ids to install from the selected source
+    pub update_and_play: bool,
+}
+
+impl CliArgs {
+    pub fn is_empty(&self) -> bool {
+        self.source.is_none() && self.install.is_empty() && !self.update_and_play
+    }
+}
+
+fn is_deep_link(arg: &str) -> bool {
+    arg.to_lowercase()
+        .starts_with(&format!("{}:", deep_link::SCHEME))
+}
+
+fn take_value(
+    name: &str,
+    inline_value: Option<&str>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, anyhow::Error> {
+    let value = match inline_value {
+        Some(value) => value.to_string(),
+        None => args
+            .next()
+            .filter(|value| !value.starts_with("--") && !is_deep_link(value))
+            .ok_or_else(|| anyhow::anyhow!("Missing value for '--{}'", name))?,
+    };
+
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err(anyhow::anyhow!("Missing value for '--{}'", name));
+    }
+
+    Ok(value)
+}
+
+/// Parses the process arguments, skipping the executable path.
+/// `llm://` links are left to the deep link handler and unknown arguments are ignored.
+pub fn parse(args: &[String]) -> Result<CliArgs, anyhow::Error> {
+    let mut cli_args = CliArgs::default();
+    let mut args = args.iter().skip(1).cloned();
+
+    while let Some(arg) = args.next() {
+        if is_deep_link(&arg) {
+            continue;
+        }
+
+        let Some(option) = arg.strip_prefix("--") else {
+            warn!("Ignoring unknown argument '{}'", arg);
+            continue;
+        };
+
+        let (name, inline_value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option, None),
+        };
+
+        match name {
+            "install" => {
+                let id = take_value(name, inline_value, &mut args)?;
+                if !cli_args.install.contains(&id) {
+                    cli_args.install.push(id);
+                }
+            }
+            "source" => cli_args.source = Some(take_value(name, inline_value, &mut args)?),
+            "update-and-play" => cli_args.update_and_play = true,
+            _ => warn!("Ignoring unknown argument '{}'", arg),
+        }
+    }
+
+    Ok(cli_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<CliArgs, anyhow::Error> {
+        let args: Vec<String> = std::iter::once("limbus-localization-manager")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        parse(&args)
+    }
+
+    #[test]
+    fn parses_no_arguments() {
+        assert!(parse_args(&[]).unwrap().is_empty());
+        assert!(parse(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parses_all_flags() {
+        let cli_args = parse_args(&[
+            "--source",
+            "Github",
+            "--install=ru",
+            "--install",
+            "en",
+            "--install",
+            "ru",
+            "--update-and-play",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            cli_args,
+            CliArgs {
+                source: Some("Github".to_string()),
+                install: vec!["ru".to_string(), "en".to_string()],
+                update_and_play: true,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_flags_without_values() {
+        for args in [
+            &["--source"][..],
+            &["--install"],
+            &["--install="],
+            &["--source", "  "],
+            &["--install", "--update-and-play"],
+            &["--source", "--install", "ru"],
+        ] {
+            let error = parse_args(args).unwrap_err();
+            assert!(
+                error.to_string().starts_with("Missing value for '--"),
+                "{:?}: {}",
+                args,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_arguments() {
+        let cli_args = parse_args(&[
+            "--verbose",
+            "-x",
+            "stray",
+            "--colour=auto",
+            "--install",
+            "ru",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            cli_args,
+            CliArgs {
+                install: vec!["ru".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn leaves_deep_links_to_their_handler() {
+        let cli_args = parse_args(&[
+            "--source",
+            "github",
+            "llm://install?id=ru&source=https://example.com/l.json",
+            "--update-and-play",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            cli_args,
+            CliArgs {
+                source: Some("github".to_string()),
+                update_and_play: true,
+                ..Default::default()
+            }
+        );
+        assert!(parse_args(&["LLM://add-source?name=Team"])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn deep_link_is_not_a_flag_value() {
+        let error = parse_args(&["--install", "llm://install?id=ru"]).unwrap_err();
+
+        assert_eq!(error.to_string(), "Missing value for '--install'");
+    }
+}
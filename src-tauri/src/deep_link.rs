@@ -0,0 +1,316 @@
+use crate::utils;
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub const SCHEME: &str = "llm";
+
+const MAX_FIELD_LENGTH: usize = 64;
+
+/// An action requested through an `llm://` link, e.g.
+/// `llm://add-source?name=Team&url=https://example.com/localizations.json`
+/// or `llm://install?id=team-en&source=https://example.com/localizations.json`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    AddSource {
+        key: String,
+        name: String,
+        url: String,
+    },
+    Install {
+        id: String,
+        source_url: String,
+    },
+}
+
+/// Turns a source name into a settings key, e.g. "My Team" -> "my-team".
+pub fn make_source_key(name: &str) -> String {
+    let key: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let key = key
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if key.is_empty() {
+        "source".to_string()
+    } else {
+        key
+    }
+}
+
+fn get_param(params: &HashMap<String, String>, name: &str) -> Result<String, anyhow::Error> {
+    let value = params
+        .get(name)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Missing '{}' parameter", name))?;
+
+    if value.chars().any(|c| c.is_control()) {
+        return Err(anyhow::anyhow!(
+            "Parameter '{}' contains control characters",
+            name
+        ));
+    }
+
+    Ok(value)
+}
+
+fn check_length(name: &str, value: &str) -> Result<(), anyhow::Error> {
+    if value.chars().count() > MAX_FIELD_LENGTH {
+        return Err(anyhow::anyhow!(
+            "Parameter '{}' is longer than {} characters",
+            name,
+            MAX_FIELD_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+pub fn parse(link: &str) -> Result<DeepLink, anyhow::Error> {
+    let url =
+        reqwest::Url::parse(link.trim()).with_context(|| format!("Invalid link '{}'", link))?;
+
+    if url.scheme() != SCHEME {
+        return Err(anyhow::anyhow!(
+            "Unsupported link scheme '{}'",
+            url.scheme()
+        ));
+    }
+
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let action = url.host_str().unwrap_or_default().to_lowercase();
+
+    match action.as_str() {
+        "add-source" => {
+            let source_url = get_param(&params, "url")?;
+            utils::validate_source_url(&source_url)?;
+
+            let name = get_param(&params, "name")?;
+            check_length("name", &name)?;
+
+            let key = match params.get("key").filter(|key| !key.trim().is_empty()) {
+                Some(key) => make_source_key(key),
+                None => make_source_key(&name),
+            };
+            check_length("key", &key)?;
+
+            Ok(DeepLink::AddSource {
+                key,
+                name,
+                url: source_url,
+            })
+        }
+        "install" => {
+            let id = get_param(&params, "id")?;
+            check_length("id", &id)?;
+            if !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            {
+                return Err(anyhow::anyhow!("Invalid localization id '{}'", id));
+            }
+
+            let source_url = get_param(&params, "source")?;
+            utils::validate_source_url(&source_url)?;
+
+            Ok(DeepLink::Install { id, source_url })
+        }
+        action => Err(anyhow::anyhow!("Unknown link action '{}'", action)),
+    }
+}
+
+/// Finds an `llm://` link among command line arguments.
+pub fn find_in_args(args: &[String]) -> Option<&String> {
+    let prefix = format!("{}:", SCHEME);
+    args.iter()
+        .skip(1)
+        .find(|arg| arg.to_lowercase().starts_with(&prefix))
+}
+
+/// Registers the app as the handler of `llm://` links for the current user,
+/// unless it already is. Runs external tools, so keep it off the main thread.
+#[cfg(target_os = "windows")]
+pub fn register_scheme() -> Result<(), anyhow::Error> {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    let exe_path = std::env::current_exe()?;
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let command = format!("\"{}\" \"%1\"", exe_path.display());
+
+    let registered_command = hkcu
+        .open_subkey(format!(
+            "Software\\Classes\\{}\\shell\\open\\command",
+            SCHEME
+        ))
+        .and_then(|command_key| command_key.get_value::<String, _>(""));
+    if registered_command.is_ok_and(|registered_command| registered_command == command) {
+        return Ok(());
+    }
+
+    let (scheme_key, _) = hkcu.create_subkey(format!("Software\\Classes\\{}", SCHEME))?;
+    scheme_key.set_value("", &"URL:Limbus Localization Manager")?;
+    scheme_key.set_value("URL Protocol", &"")?;
+
+    let (command_key, _) = scheme_key.create_subkey("shell\\open\\command")?;
+    command_key.set_value("", &command)?;
+
+    Ok(())
+}
+
+/// Registers the app as the handler of `llm://` links for the current user,
+/// unless it already is. Runs external tools, so keep it off the main thread.
+#[cfg(target_os = "linux")]
+pub fn register_scheme() -> Result<(), anyhow::Error> {
+    // AppImages run from a temporary mount, the image itself is what should be launched
+    let exe_path = match std::env::var_os("APPIMAGE") {
+        Some(appimage) => std::path::PathBuf::from(appimage),
+        None => std::env::current_exe()?,
+    };
+
+    let applications_dir = dirs::data_dir()
+        .ok_or_else(|| anyhow::anyhow!("Failed to find data directory"))?
+        .join("applications");
+    std::fs::create_dir_all(&applications_dir)?;
+
+    let desktop_file_name = "limbus-localization-manager-handler.desktop";
+    let desktop_entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Limbus Localization Manager\nExec=\"{}\" %u\nTerminal=false\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+        exe_path.display(),
+        SCHEME
+    );
+    let desktop_file_path = applications_dir.join(desktop_file_name);
+    let mime_type = format!("x-scheme-handler/{}", SCHEME);
+
+    let is_current = std::fs::read_to_string(&desktop_file_path)
+        .is_ok_and(|existing_entry| existing_entry == desktop_entry);
+    if is_current {
+        let default_handler = std::process::Command::new("xdg-mime")
+            .args(["query", "default", &mime_type])
+            .output()
+            .context("Failed to run xdg-mime")?;
+
+        if String::from_utf8_lossy(&default_handler.stdout).trim() == desktop_file_name {
+            return Ok(());
+        }
+    } else {
+        std::fs::write(&desktop_file_path, desktop_entry)?;
+    }
+
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", desktop_file_name, &mime_type])
+        .status()
+        .context("Failed to run xdg-mime")?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("xdg-mime exited with {}", status));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_add_source_link() {
+        let link = parse(
+            "llm://add-source?name=My%20Team&url=https%3A%2F%2Fexample.com%2Flocalizations.json",
+        )
+        .unwrap();
+
+        assert_eq!(
+            link,
+            DeepLink::AddSource {
+                key: "my-team".to_string(),
+                name: "My Team".to_string(),
+                url: "https://example.com/localizations.json".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_add_source_link_with_key() {
+        let link = parse("LLM://Add-Source?name=Team&key=Team%20EN&url=https://example.com/l.json")
+            .unwrap();
+
+        assert!(matches!(link, DeepLink::AddSource { key, .. } if key == "team-en"));
+    }
+
+    #[test]
+    fn parses_install_link() {
+        let link = parse("llm://install?id=team-en_1.0&source=https://example.com/l.json").unwrap();
+
+        assert_eq!(
+            link,
+            DeepLink::Install {
+                id: "team-en_1.0".to_string(),
+                source_url: "https://example.com/l.json".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_links() {
+        let long_name = "a".repeat(MAX_FIELD_LENGTH + 1);
+        let links = [
+            "",
+            "not a link",
+            "https://example.com/l.json",
+            "llm://",
+            "llm://remove-source?key=team",
+            "llm://add-source?name=Team",
+            "llm://add-source?url=https://example.com/l.json",
+            "llm://add-source?name=%20&url=https://example.com/l.json",
+            "llm://add-source?name=Team&url=file:///etc/passwd",
+            "llm://add-source?name=Team&url=javascript:alert(1)",
+            "llm://add-source?name=Te%0Aam&url=https://example.com/l.json",
+            &format!(
+                "llm://add-source?name={}&url=https://example.com/l.json",
+                long_name
+            ),
+            "llm://install?id=team-en",
+            "llm://install?source=https://example.com/l.json",
+            "llm://install?id=../../etc&source=https://example.com/l.json",
+            "llm://install?id=team en&source=https://example.com/l.json",
+            "llm://install?id=team-en&source=ftp://example.com/l.json",
+        ];
+
+        for link in links {
+            assert!(parse(link).is_err(), "accepted '{}'", link);
+        }
+    }
+
+    #[test]
+    fn makes_source_keys() {
+        assert_eq!(make_source_key("  My Team (EN)  "), "my-team-en");
+        assert_eq!(make_source_key("Русский"), "русский");
+        assert_eq!(make_source_key("!!!"), "source");
+    }
+
+    #[test]
+    fn finds_link_in_args() {
+        let args = [
+            "limbus-localization-manager".to_string(),
+            "--source".to_string(),
+            "github".to_string(),
+            "LLM://install?id=en".to_string(),
+        ];
+
+        assert_eq!(
+            find_in_args(&args).map(String::as_str),
+            Some("LLM://install?id=en")
+        );
+        assert!(find_in_args(&args[..3]).is_none());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::utils::{self, InstalledMetadata};
+use anyhow::Context;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// Font hash to the ids of installed localizations using it.
+pub type FontReferences = HashMap<String, Vec<String>>;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CachedFont {
+    pub file_name: String,
+    pub hash: String,
+    pub size: u64,
+    pub referenced_by: Vec<String>, // Ids of installed localizations using the font
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FontCacheReport {
+    pub path: PathBuf,
+    pub fonts: Vec<CachedFont>,
+    pub total_size: u64,
+    pub unreferenced_size: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PruneResult {
+    pub removed: Vec<String>,
+    pub freed_size: u64,
+}
+
+/// Resolves the shared font cache, defaulting to `FontCache` in the app cache dir.
+pub fn get_font_cache_dir(
+    app_handle: &tauri::AppHandle,
+    font_cache_directory: Option<&str>,
+) -> Result<PathBuf, anyhow::Error> {
+    match font_cache_directory {
+        Some(directory) => Ok(PathBuf::from(directory)),
+        None => Ok(app_handle.path().app_cache_dir()?.join("FontCache")),
+    }
+}
+
+/// Location of the per-game cache used by older versions.
+pub fn get_legacy_font_cache_dir(game_path: &Path) -> PathBuf {
+    game_path.join("FontCache")
+}
+
+/// Moves cached fonts from `from` into `to` and removes `from` once it is empty.
+/// Fonts already present in `to` are kept, since cache entries are named by hash.
+pub fn migrate_font_cache(from: &Path, to: &Path) -> Result<usize, anyhow::Error> {
+    if !from.is_dir() || from == to {
+        return Ok(0);
+    }
+
+    fs::create_dir_all(to).with_context(|| format!("Failed to create font cache {:?}", to))?;
+
+    let mut moved = 0;
+    for entry in
+        fs::read_dir(from).with_context(|| format!("Failed to read font cache {:?}", from))?
+    {
+        let entry = entry.with_context(|| format!("Error reading entry in {:?}", from))?;
+        let source_path = entry.path();
+
+        if !source_path.is_file() {
+            continue;
+        }
+
+        let target_path = to.join(entry.file_name());
+        if target_path.exists() {
+            fs::remove_file(&source_path)
+                .with_context(|| format!("Failed to remove cached font {:?}", source_path))?;
+            continue;
+        }
+
+        // Renaming fails across filesystems, fall back to copying
+        if fs::rename(&source_path, &target_path).is_err() {
+            fs::copy(&source_path, &target_path).with_context(|| {
+                format!("Failed to copy font {:?} to {:?}", source_path, target_path)
+            })?;
+            fs::remove_file(&source_path)
+                .with_context(|| format!("Failed to remove cached font {:?}", source_path))?;
+        }
+        moved += 1;
+    }
+
+    if let Err(e) = fs::remove_dir(from) {
+        warn!("Failed to remove old font cache {:?}: {}", from, e);
+    }
+
+    info!("Moved {} fonts from {:?} to {:?}", moved, from, to);
+    Ok(moved)
+}
+
+/// Maps font hashes to the ids of installed localizations that use them,
+/// across every game directory sharing the cache.
+/// Entries installed before fonts were recorded are hashed from their Font folder.
+pub fn collect_font_references(installations: &[(PathBuf, InstalledMetadata)]) -> FontReferences {
+    let mut references = FontReferences::new();
+
+    for (game_path, metadata) in installations {
+        for installed in metadata.installed.values() {
+            let hashes = match &installed.fonts {
+                Some(fonts) => fonts.iter().map(|font| font.hash.clone()).collect(),
+                None => hash_installed_fonts(game_path, &installed.id),
+            };
+
+            for hash in hashes {
+                let referenced_by = references.entry(hash).or_default();
+                if !referenced_by.contains(&installed.id) {
+                    referenced_by.push(installed.id.clone());
+                }
+            }
+        }
+    }
+
+    references
+}
+
+fn hash_installed_fonts(game_path: &Path, localization_id: &str) -> Vec<String> {
+    let fonts_dir = game_path
+        .join("LimbusCompany_Data")
+        .join("Lang")
+        .join(localization_id)
+        .join("Font");
+
+    let entries = match fs::read_dir(&fonts_dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            utils::calculate_md5(&path)
+                .map_err(|e| warn!("Failed to hash installed font {:?}: {}", path, e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Cached fonts are stored as `<md5>.ttf` or `<md5>.otf`. Anything else, like a
+/// `.tmp_download` file of a download in progress, is not a cache entry.
+fn get_cached_font_hash(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    if extension != "ttf" && extension != "otf" {
+        return None;
+    }
+
+    let hash = path.file_stem()?.to_str()?;
+    if hash.len() != 32 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(hash.to_lowercase())
+}
+
+pub fn list_cached_fonts(
+    cache_dir: &Path,
+    references: &FontReferences,
+) -> Result<FontCacheReport, anyhow::Error> {
+    let mut fonts = Vec::new();
+
+    if cache_dir.is_dir() {
+        for entry in fs::read_dir(cache_dir)
+            .with_context(|| format!("Failed to read font cache {:?}", cache_dir))?
+        {
+            let entry = entry.with_context(|| format!("Error reading entry in {:?}", cache_dir))?;
+            let path = entry.path();
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let Some(hash) = get_cached_font_hash(&path) else {
+                continue;
+            };
+
+            let size = entry
+                .metadata()
+                .with_context(|| format!("Failed to get size of {:?}", path))?
+                .len();
+
+            let mut referenced_by = references.get(&hash).cloned().unwrap_or_default();
+            referenced_by.sort();
+
+            fonts.push(CachedFont {
+                file_name: entry.file_name().to_string_lossy().to_string(),
+                hash,
+                size,
+                referenced_by,
+            });
+        }
+    }
+
+    fonts.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+    let total_size = fonts.iter().map(|font| font.size).sum();
+    let unreferenced_size = fonts
+        .iter()
+        .filter(|font| font.referenced_by.is_empty())
+        .map(|font| font.size)
+        .sum();
+
+    Ok(FontCacheReport {
+        path: cache_dir.to_path_buf(),
+        fonts,
+        total_size,
+        unreferenced_size,
+    })
+}
+
+/// Deletes cached fonts that no installed localization references.
+pub fn prune_font_cache(
+    cache_dir: &Path,
+    references: &FontReferences,
+) -> Result<PruneResult, anyhow::Error> {
+    let report = list_cached_fonts(cache_dir, references)?;
+    let mut result = PruneResult {
+        removed: Vec::new(),
+        freed_size: 0,
+    };
+
+    for font in report.fonts {
+        if !font.referenced_by.is_empty() {
+            continue;
+        }
+
+        let path = cache_dir.join(&font.file_name);
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove cached font {:?}", path))?;
+
+        result.freed_size += font.size;
+        result.removed.push(font.file_name);
+    }
+
+    info!(
+        "Pruned {} fonts ({} bytes) from {:?}",
+        result.removed.len(),
+        result.freed_size,
+        cache_dir
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USED_HASH: &str = "0123456789abcdef0123456789abcdef";
+    const UNUSED_HASH: &str = "fedcba9876543210fedcba9876543210";
+
+    fn write_cache_file(cache_dir: &Path, file_name: &str) {
+        fs::write(cache_dir.join(file_name), b"font").unwrap();
+    }
+
+    #[test]
+    fn prune_removes_only_unreferenced_fonts() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        write_cache_file(cache_dir.path(), &format!("{}.ttf", USED_HASH));
+        write_cache_file(cache_dir.path(), &format!("{}.otf", UNUSED_HASH));
+
+        let references = FontReferences::from([(USED_HASH.to_string(), vec!["ru".to_string()])]);
+        let result = prune_font_cache(cache_dir.path(), &references).unwrap();
+
+        assert_eq!(result.removed, vec![format!("{}.otf", UNUSED_HASH)]);
+        assert_eq!(result.freed_size, 4);
+        assert!(cache_dir.path().join(format!("{}.ttf", USED_HASH)).exists());
+    }
+
+    #[test]
+    fn prune_skips_temporary_and_foreign_files() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let kept = [
+            format!("{}.tmp_download", UNUSED_HASH),
+            format!(".tmp{}.ttf", UNUSED_HASH),
+            "readme.txt".to_string(),
+            "notes.ttf".to_string(),
+        ];
+        for file_name in &kept {
+            write_cache_file(cache_dir.path(), file_name);
+        }
+
+        let report = list_cached_fonts(cache_dir.path(), &FontReferences::new()).unwrap();
+        assert!(report.fonts.is_empty());
+
+        let result = prune_font_cache(cache_dir.path(), &FontReferences::new()).unwrap();
+        assert!(result.removed.is_empty());
+        for file_name in &kept {
+            assert!(cache_dir.path().join(file_name).exists(), "{}", file_name);
+        }
+    }
+}
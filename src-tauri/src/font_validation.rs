@@ -0,0 +1,459 @@
+use anyhow::Context;
+use std::fs;
+use std::path::Path;
+
+const REQUIRED_TABLES: [&[u8; 4]; 3] = [b"cmap", b"head", b"maxp"];
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, anyhow::Error> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow::anyhow!("Unexpected end of font data at offset {}", offset))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, anyhow::Error> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow::anyhow!("Unexpected end of font data at offset {}", offset))
+}
+
+struct TableRecord {
+    tag: [u8; 4],
+    offset: usize,
+    length: usize,
+}
+
+/// Parses the sfnt table directory starting at `font_offset`.
+fn read_table_directory(
+    data: &[u8],
+    font_offset: usize,
+) -> Result<Vec<TableRecord>, anyhow::Error> {
+    let sfnt_version = read_u32(data, font_offset)?;
+    if !matches!(
+        &sfnt_version.to_be_bytes(),
+        [0x00, 0x01, 0x00, 0x00] | b"OTTO" | b"true"
+    ) {
+        return Err(anyhow::anyhow!(
+            "Unknown font signature {:#010x}",
+            sfnt_version
+        ));
+    }
+
+    let num_tables = read_u16(data, font_offset + 4)? as usize;
+    if num_tables == 0 {
+        return Err(anyhow::anyhow!("Font has no tables"));
+    }
+
+    let mut tables = Vec::with_capacity(num_tables);
+    for i in 0..num_tables {
+        let record_offset = font_offset + 12 + i * 16;
+        let tag_bytes = data
+            .get(record_offset..record_offset + 4)
+            .ok_or_else(|| anyhow::anyhow!("Table directory is truncated"))?;
+        let mut tag = [0; 4];
+        tag.copy_from_slice(tag_bytes);
+
+        let offset = read_u32(data, record_offset + 8)? as usize;
+        let length = read_u32(data, record_offset + 12)? as usize;
+
+        if offset
+            .checked_add(length)
+            .is_none_or(|end| end > data.len())
+        {
+            return Err(anyhow::anyhow!(
+                "Table '{}' points outside of the file",
+                String::from_utf8_lossy(&tag)
+            ));
+        }
+
+        tables.push(TableRecord {
+            tag,
+            offset,
+            length,
+        });
+    }
+
+    for required in REQUIRED_TABLES {
+        if !tables.iter().any(|table| &table.tag == required) {
+            return Err(anyhow::anyhow!(
+                "Required table '{}' is missing",
+                String::from_utf8_lossy(required)
+            ));
+        }
+    }
+
+    Ok(tables)
+}
+
+fn describe_payload(data: &[u8]) -> &'static str {
+    let start = String::from_utf8_lossy(&data[..data.len().min(64)]).to_lowercase();
+    let start = start.trim_start();
+
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        "an HTML page"
+    } else if start.starts_with('<') {
+        "an XML/HTML document"
+    } else if start.starts_with('{') {
+        "a JSON document"
+    } else if data.starts_with(b"PK") {
+        "a ZIP archive"
+    } else if data.starts_with(b"wOFF") || data.starts_with(b"wOF2") {
+        "a WOFF web font, which the game cannot load"
+    } else {
+        "not a TrueType/OpenType font"
+    }
+}
+
+/// Looks up the glyph id of `c` in a format 4 or format 12 cmap subtable.
+fn lookup_glyph(data: &[u8], subtable: usize, c: u32) -> Result<Option<u16>, anyhow::Error> {
+    match read_u16(data, subtable)? {
+        4 => {
+            if c > 0xFFFF {
+                return Ok(None);
+            }
+
+            let seg_count = read_u16(data, subtable + 6)? as usize / 2;
+            let end_codes = subtable + 14;
+            let start_codes = end_codes + seg_count * 2 + 2;
+            let id_deltas = start_codes + seg_count * 2;
+            let id_range_offsets = id_deltas + seg_count * 2;
+
+            for i in 0..seg_count {
+                let end_code = read_u16(data, end_codes + i * 2)? as u32;
+                if end_code < c {
+                    continue;
+                }
+
+                let start_code = read_u16(data, start_codes + i * 2)? as u32;
+                if start_code > c {
+                    return Ok(None);
+                }
+
+                let id_delta = read_u16(data, id_deltas + i * 2)?;
+                let id_range_offset_position = id_range_offsets + i * 2;
+                let id_range_offset = read_u16(data, id_range_offset_position)? as usize;
+
+                let glyph = if id_range_offset == 0 {
+                    (c as u16).wrapping_add(id_delta)
+                } else {
+                    let glyph_position =
+                        id_range_offset_position + id_range_offset + (c - start_code) as usize * 2;
+                    match read_u16(data, glyph_position)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(id_delta),
+                    }
+                };
+
+                return Ok(Some(glyph).filter(|&glyph| glyph != 0));
+            }
+
+            Ok(None)
+        }
+        12 => {
+            let num_groups = read_u32(data, subtable + 12)? as usize;
+
+            for i in 0..num_groups {
+                let group = subtable + 16 + i * 12;
+                let start_char = read_u32(data, group)?;
+                let end_char = read_u32(data, group + 4)?;
+
+                if (start_char..=end_char).contains(&c) {
+                    let glyph = read_u32(data, group + 8)?.wrapping_add(c - start_char);
+                    return Ok(Some(glyph as u16).filter(|&glyph| glyph != 0));
+                }
+            }
+
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the characters of `required` that no Unicode cmap subtable maps to a glyph.
+fn find_missing_glyphs(
+    data: &[u8],
+    cmap: &TableRecord,
+    required: &str,
+) -> Result<Vec<char>, anyhow::Error> {
+    let num_subtables = read_u16(data, cmap.offset + 2)? as usize;
+    let mut subtables = Vec::new();
+
+    for i in 0..num_subtables {
+        let record = cmap.offset + 4 + i * 8;
+        let platform_id = read_u16(data, record)?;
+        let encoding_id = read_u16(data, record + 2)?;
+        let offset = read_u32(data, record + 4)? as usize;
+
+        // Unicode platform, or Windows with BMP/full Unicode encodings
+        let is_unicode = platform_id == 0 || (platform_id == 3 && matches!(encoding_id, 1 | 10));
+        if is_unicode && offset < cmap.length {
+            subtables.push(cmap.offset + offset);
+        }
+    }
+
+    if subtables.is_empty() {
+        return Err(anyhow::anyhow!("Font has no Unicode character map"));
+    }
+
+    let mut missing = Vec::new();
+    for c in required.chars() {
+        if c.is_whitespace() || missing.contains(&c) {
+            continue;
+        }
+
+        let mut found = false;
+        for &subtable in &subtables {
+            if lookup_glyph(data, subtable, c as u32)?.is_some() {
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            missing.push(c);
+        }
+    }
+
+    Ok(missing)
+}
+
+/// Checks that `data` is a TrueType/OpenType font (or collection) and,
+/// when `required_glyphs` is given, that it has a glyph for each of its characters.
+pub fn validate_font(data: &[u8], required_glyphs: Option<&str>) -> Result<(), anyhow::Error> {
+    if data.len() < 12 {
+        return Err(anyhow::anyhow!(
+            "File is too small to be a font ({} bytes)",
+            data.len()
+        ));
+    }
+
+    let is_font = matches!(
+        &data[..4],
+        [0x00, 0x01, 0x00, 0x00] | b"OTTO" | b"true" | b"ttcf"
+    );
+    if !is_font {
+        return Err(anyhow::anyhow!("File is {}", describe_payload(data)));
+    }
+
+    // Collections are validated using their first font
+    let font_offset = if data.starts_with(b"ttcf") {
+        read_u32(data, 12)? as usize
+    } else {
+        0
+    };
+
+    let tables = read_table_directory(data, font_offset)?;
+
+    if let Some(required_glyphs) = required_glyphs {
+        let cmap = tables
+            .iter()
+            .find(|table| &table.tag == b"cmap")
+            .ok_or_else(|| anyhow::anyhow!("Required table 'cmap' is missing"))?;
+
+        let missing = find_missing_glyphs(data, cmap, required_glyphs)?;
+        if !missing.is_empty() {
+            let missing: String = missing.into_iter().take(32).collect();
+            return Err(anyhow::anyhow!("Font has no glyphs for: {}", missing));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_font_file(path: &Path, required_glyphs: Option<&str>) -> Result<(), anyhow::Error> {
+    let data = fs::read(path).with_context(|| format!("Failed to read font file {:?}", path))?;
+    validate_font(&data, required_glyphs).with_context(|| format!("Invalid font file {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_u16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn push_u32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Format 4 subtable mapping 'A'..='C' to glyphs 1..=3 through `idDelta`
+    /// and 'Ж' to glyph 7 through `idRangeOffset`.
+    fn format_4_subtable() -> Vec<u8> {
+        let segments: [(u16, u16, u16, u16); 3] = [
+            (0x41, 0x43, 1u16.wrapping_sub(0x41), 0),
+            (0x416, 0x416, 0, 4), // Points at the first glyphIdArray entry
+            (0xFFFF, 0xFFFF, 1, 0),
+        ];
+        let seg_count = segments.len() as u16;
+
+        let mut subtable = Vec::new();
+        push_u16(&mut subtable, 4);
+        push_u16(&mut subtable, 0); // Length, not checked
+        push_u16(&mut subtable, 0);
+        push_u16(&mut subtable, seg_count * 2);
+        push_u16(&mut subtable, 4);
+        push_u16(&mut subtable, 1);
+        push_u16(&mut subtable, 2);
+        for (_, end_code, _, _) in segments {
+            push_u16(&mut subtable, end_code);
+        }
+        push_u16(&mut subtable, 0);
+        for (start_code, _, _, _) in segments {
+            push_u16(&mut subtable, start_code);
+        }
+        for (_, _, id_delta, _) in segments {
+            push_u16(&mut subtable, id_delta);
+        }
+        for (_, _, _, id_range_offset) in segments {
+            push_u16(&mut subtable, id_range_offset);
+        }
+        push_u16(&mut subtable, 7);
+        subtable
+    }
+
+    /// Format 12 subtable mapping U+1F600..=U+1F601 to glyphs 10..=11.
+    fn format_12_subtable() -> Vec<u8> {
+        let mut subtable = Vec::new();
+        push_u16(&mut subtable, 12);
+        push_u16(&mut subtable, 0);
+        push_u32(&mut subtable, 28);
+        push_u32(&mut subtable, 0);
+        push_u32(&mut subtable, 1);
+        push_u32(&mut subtable, 0x1F600);
+        push_u32(&mut subtable, 0x1F601);
+        push_u32(&mut subtable, 10);
+        subtable
+    }
+
+    /// Builds a TrueType font with placeholder `head` and `maxp` tables
+    /// and a `cmap` with one subtable. `cmap` comes last, so any truncation breaks it.
+    fn build_font(platform_id: u16, encoding_id: u16, subtable: &[u8]) -> Vec<u8> {
+        let mut cmap = Vec::new();
+        push_u16(&mut cmap, 0);
+        push_u16(&mut cmap, 1);
+        push_u16(&mut cmap, platform_id);
+        push_u16(&mut cmap, encoding_id);
+        push_u32(&mut cmap, 12);
+        cmap.extend_from_slice(subtable);
+
+        let tables: [(&[u8; 4], Vec<u8>); 3] = [
+            (b"head", vec![0; 8]),
+            (b"maxp", vec![0; 8]),
+            (b"cmap", cmap),
+        ];
+
+        let mut font = Vec::new();
+        push_u32(&mut font, 0x00010000);
+        push_u16(&mut font, tables.len() as u16);
+        push_u16(&mut font, 32);
+        push_u16(&mut font, 1);
+        push_u16(&mut font, 16);
+
+        let mut offset = 12 + tables.len() * 16;
+        for (tag, table) in &tables {
+            font.extend_from_slice(*tag);
+            push_u32(&mut font, 0); // Checksum, not checked
+            push_u32(&mut font, offset as u32);
+            push_u32(&mut font, table.len() as u32);
+            offset += table.len();
+        }
+        for (_, table) in &tables {
+            font.extend_from_slice(table);
+        }
+        font
+    }
+
+    #[test]
+    fn accepts_format_4_font() {
+        let font = build_font(3, 1, &format_4_subtable());
+
+        validate_font(&font, None).unwrap();
+        validate_font(&font, Some("ABC Ж")).unwrap();
+    }
+
+    #[test]
+    fn reports_missing_format_4_glyphs() {
+        let font = build_font(3, 1, &format_4_subtable());
+
+        let error = validate_font(&font, Some("ABDЖЗ")).unwrap_err();
+        assert_eq!(error.to_string(), "Font has no glyphs for: DЗ");
+    }
+
+    #[test]
+    fn accepts_format_12_font() {
+        let font = build_font(3, 10, &format_12_subtable());
+
+        validate_font(&font, Some("\u{1F600}\u{1F601}")).unwrap();
+        assert!(validate_font(&font, Some("\u{1F602}")).is_err());
+        assert!(validate_font(&font, Some("A")).is_err());
+    }
+
+    #[test]
+    fn requires_unicode_character_map() {
+        let font = build_font(1, 0, &format_4_subtable());
+
+        validate_font(&font, None).unwrap();
+        let error = validate_font(&font, Some("A")).unwrap_err();
+        assert_eq!(error.to_string(), "Font has no Unicode character map");
+    }
+
+    #[test]
+    fn rejects_truncated_fonts() {
+        for subtable in [format_4_subtable(), format_12_subtable()] {
+            let font = build_font(0, 3, &subtable);
+
+            for length in 0..font.len() {
+                assert!(
+                    validate_font(&font[..length], Some("A\u{1F600}")).is_err(),
+                    "accepted font truncated to {} bytes",
+                    length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let payloads: [&[u8]; 5] = [
+            b"<!DOCTYPE html><html><body>Not found</body></html>",
+            b"{\"error\": \"rate limited\"}",
+            b"PK\x03\x04\x14\x00\x00\x00\x08\x00\x00\x00",
+            b"wOFF\x00\x01\x00\x00\x00\x00\x00\x00",
+            &[0xAB; 256],
+        ];
+        for payload in payloads {
+            assert!(validate_font(payload, None).is_err());
+        }
+
+        // Valid signatures followed by nonsense
+        let mut garbage = vec![0x00, 0x01, 0x00, 0x00, 0xFF, 0xFF];
+        garbage.extend_from_slice(&[0xFF; 64]);
+        assert!(validate_font(&garbage, Some("A")).is_err());
+
+        let mut collection = b"ttcf\x00\x01\x00\x00\x00\x00\x00\x01".to_vec();
+        push_u32(&mut collection, u32::MAX);
+        assert!(validate_font(&collection, None).is_err());
+    }
+
+    #[test]
+    fn rejects_lookups_past_the_end() {
+        let mut subtable = format_12_subtable();
+        subtable[12..16].copy_from_slice(&u32::MAX.to_be_bytes());
+        let font = build_font(3, 10, &subtable);
+        assert!(validate_font(&font, Some("\u{1F602}")).is_err());
+
+        let mut subtable = format_4_subtable();
+        subtable[6..8].copy_from_slice(&u16::MAX.to_be_bytes());
+        let font = build_font(3, 1, &subtable);
+        assert!(validate_font(&font, Some("\u{FFFE}")).is_err());
+    }
+
+    #[test]
+    fn rejects_font_without_required_tables() {
+        let mut font = build_font(3, 1, &format_4_subtable());
+        font[12..16].copy_from_slice(b"name");
+
+        let error = validate_font(&font, None).unwrap_err();
+        assert_eq!(error.to_string(), "Required table 'head' is missing");
+    }
+}
@@ -0,0 +1,60 @@
+use crate::steam;
+use log::{error, info};
+use std::time::Duration;
+use sysinfo::System;
+use tauri::Emitter;
+use tokio::sync::watch;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Tracks the game process in the background and emits
+/// `game:started` / `game:exited` when its state changes.
+pub struct GameWatcher {
+    running: watch::Receiver<bool>,
+}
+
+impl GameWatcher {
+    pub fn start(app_handle: tauri::AppHandle) -> Self {
+        let mut system = System::new();
+        steam::refresh_processes(&mut system);
+
+        let (sender, receiver) = watch::channel(steam::has_game_process(&system));
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                steam::refresh_processes(&mut system);
+                let is_running = steam::has_game_process(&system);
+
+                if *sender.borrow() == is_running {
+                    continue;
+                }
+
+                sender.send_replace(is_running);
+
+                let event = if is_running {
+                    info!("Game process started");
+                    "game:started"
+                } else {
+                    info!("Game process exited");
+                    "game:exited"
+                };
+
+                if let Err(e) = app_handle.emit(event, ()) {
+                    error!("Failed to emit {}: {:?}", event, e);
+                }
+            }
+        });
+
+        Self { running: receiver }
+    }
+
+    pub fn is_running(&self) -> bool {
+        *self.running.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.running.clone()
+    }
+}
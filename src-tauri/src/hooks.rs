@@ -0,0 +1,224 @@
+use crate::launcher;
+use crate::settings::Hook;
+use anyhow::Context;
+use log::{error, info, warn};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::process::Command;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    PreLaunch,
+    PostExit,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct HookEvent {
+    stage: HookStage,
+    name: String,
+    exit_code: Option<i32>,
+    error: Option<String>,
+}
+
+/// Runs hooks one after another, emitting `play:hook_started`, `play:hook_finished`
+/// and `play:hook_failed`. Stops at the first failing hook unless it allows errors.
+pub async fn run_hooks(
+    app_handle: &tauri::AppHandle,
+    stage: HookStage,
+    hooks: &[Hook],
+    game_path: &Path,
+) -> Result<(), anyhow::Error> {
+    run_hooks_with(stage, hooks, game_path, |name, event| {
+        app_handle.emit(name, event).unwrap();
+    })
+    .await
+}
+
+async fn run_hooks_with(
+    stage: HookStage,
+    hooks: &[Hook],
+    game_path: &Path,
+    mut emit: impl FnMut(&str, &HookEvent),
+) -> Result<(), anyhow::Error> {
+    for hook in hooks {
+        info!("Running {:?} hook '{}'", stage, hook.name);
+
+        let mut event = HookEvent {
+            stage,
+            name: hook.name.clone(),
+            exit_code: None,
+            error: None,
+        };
+        emit("play:hook_started", &event);
+
+        let result = run_hook(hook, game_path).await;
+
+        match result {
+            Ok(0) => {
+                event.exit_code = Some(0);
+                emit("play:hook_finished", &event);
+                continue;
+            }
+            Ok(exit_code) => {
+                event.exit_code = Some(exit_code);
+                event.error = Some(format!("Exited with code {}", exit_code));
+            }
+            Err(e) => {
+                event.error = Some(e.to_string());
+            }
+        }
+
+        emit("play:hook_failed", &event);
+
+        let error = event.error.unwrap_or_default();
+        if hook.continue_on_error {
+            warn!("Hook '{}' failed: {}. Continuing", hook.name, error);
+            continue;
+        }
+
+        error!("Hook '{}' failed: {}", hook.name, error);
+        return Err(anyhow::anyhow!("Hook '{}' failed: {}", hook.name, error));
+    }
+
+    Ok(())
+}
+
+async fn run_hook(hook: &Hook, game_path: &Path) -> Result<i32, anyhow::Error> {
+    let command_line: Vec<String> = launcher::split_command_line(&hook.command)?
+        .iter()
+        .map(|arg| launcher::expand_placeholders(arg, game_path))
+        .collect();
+
+    let (program, args) = command_line
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Hook command is empty"))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(game_path)
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))?;
+
+    let status = tokio::time::timeout(Duration::from_secs(hook.timeout_secs), child.wait())
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out after {} seconds", hook.timeout_secs))?
+        .with_context(|| format!("Failed to wait for {}", program))?;
+
+    // A process killed by a signal has no exit code
+    Ok(status.code().unwrap_or(-1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn hook(name: &str, command: &str, continue_on_error: bool) -> Hook {
+        Hook {
+            name: name.to_string(),
+            command: command.to_string(),
+            timeout_secs: 5,
+            continue_on_error,
+        }
+    }
+
+    /// Runs the hooks and returns the emitted events as `(event, hook name)`.
+    async fn run(
+        hooks: &[Hook],
+        game_path: &Path,
+    ) -> (Result<(), anyhow::Error>, Vec<(String, String)>) {
+        let mut events = Vec::new();
+        let result = run_hooks_with(HookStage::PreLaunch, hooks, game_path, |name, event| {
+            events.push((name.to_string(), event.name.clone()));
+        })
+        .await;
+        (result, events)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_hook_stops_the_chain() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let hooks = [
+            hook("fail", "sh -c 'exit 3'", false),
+            hook("touch", "touch touched", false),
+        ];
+
+        let (result, events) = run(&hooks, game_dir.path()).await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Exited with code 3"));
+        assert_eq!(
+            events,
+            vec![
+                ("play:hook_started".to_string(), "fail".to_string()),
+                ("play:hook_failed".to_string(), "fail".to_string()),
+            ]
+        );
+        assert!(!game_dir.path().join("touched").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_hook_can_continue_on_error() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let hooks = [
+            hook("fail", "sh -c 'exit 3'", true),
+            hook("touch", "touch touched", false),
+        ];
+
+        let (result, events) = run(&hooks, game_dir.path()).await;
+
+        result.unwrap();
+        assert_eq!(
+            events,
+            vec![
+                ("play:hook_started".to_string(), "fail".to_string()),
+                ("play:hook_failed".to_string(), "fail".to_string()),
+                ("play:hook_started".to_string(), "touch".to_string()),
+                ("play:hook_finished".to_string(), "touch".to_string()),
+            ]
+        );
+        assert!(game_dir.path().join("touched").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn slow_hook_is_killed_after_the_timeout() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let hooks = [Hook {
+            timeout_secs: 1,
+            ..hook("slow", "sh -c 'sleep 30 && touch touched'", false)
+        }];
+
+        let started = std::time::Instant::now();
+        let (result, events) = run(&hooks, game_dir.path()).await;
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Timed out after 1 seconds"));
+        assert_eq!(events.last().unwrap().0, "play:hook_failed");
+        assert!(!game_dir.path().join("touched").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hooks_run_in_the_game_directory() {
+        let game_dir = tempfile::tempdir().unwrap();
+        fs::write(game_dir.path().join("LimbusCompany.exe"), b"MZ").unwrap();
+        let hooks = [hook("copy", "cp {game_exe} copied.exe", false)];
+
+        let (result, _) = run(&hooks, game_dir.path()).await;
+
+        result.unwrap();
+        assert_eq!(fs::read(game_dir.path().join("copied.exe")).unwrap(), b"MZ");
+    }
+}
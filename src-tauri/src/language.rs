@@ -0,0 +1,149 @@
+use crate::utils::InstalledMetadata;
+use anyhow::Context;
+use log::{debug, info};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const LANG_CONFIG_FILE_NAME: &str = "config.json";
+const LANG_KEY: &str = "lang";
+
+fn get_lang_directory(game_path: &Path) -> PathBuf {
+    game_path.join("LimbusCompany_Data").join("Lang")
+}
+
+fn get_lang_config_path(game_path: &Path) -> PathBuf {
+    get_lang_directory(game_path).join(LANG_CONFIG_FILE_NAME)
+}
+
+fn read_lang_config(game_path: &Path) -> Result<serde_json::Value, anyhow::Error> {
+    let config_path = get_lang_config_path(game_path);
+
+    if !config_path.exists() {
+        return Ok(serde_json::Value::Object(serde_json::Map::new()));
+    }
+
+    let config_content = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read language config {:?}", config_path))?;
+
+    // The file is written by the game and may contain a BOM
+    let config: serde_json::Value =
+        serde_json::from_str(config_content.trim_start_matches('\u{feff}'))
+            .with_context(|| format!("Failed to parse language config {:?}", config_path))?;
+
+    if !config.is_object() {
+        return Err(anyhow::anyhow!(
+            "Language config {:?} is not a JSON object",
+            config_path
+        ));
+    }
+
+    Ok(config)
+}
+
+/// Returns the language folder currently selected in the game, if any.
+pub fn get_selected_language(game_path: &Path) -> Result<Option<String>, anyhow::Error> {
+    let config = read_lang_config(game_path)?;
+
+    Ok(config
+        .get(LANG_KEY)
+        .and_then(|lang| lang.as_str())
+        .filter(|lang| !lang.is_empty())
+        .map(|lang| lang.to_string()))
+}
+
+/// Points the game at `Lang/<language>`, or back to its built-in language when `None`.
+/// Other keys of the config are preserved.
+pub fn set_selected_language(
+    game_path: &Path,
+    language: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    if let Some(language) = language {
+        if !is_language_available(game_path, language) {
+            return Err(anyhow::anyhow!("Language '{}' is not installed", language));
+        }
+    }
+
+    let mut config = read_lang_config(game_path)?;
+    let config_object = config.as_object_mut().unwrap();
+
+    match language {
+        Some(language) => {
+            config_object.insert(
+                LANG_KEY.to_string(),
+                serde_json::Value::String(language.to_string()),
+            );
+        }
+        None => {
+            config_object.remove(LANG_KEY);
+        }
+    }
+
+    let lang_dir = get_lang_directory(game_path);
+    fs::create_dir_all(&lang_dir)
+        .with_context(|| format!("Failed to create Lang directory {:?}", lang_dir))?;
+
+    let config_path = get_lang_config_path(game_path);
+    let config_content = serde_json::to_string_pretty(&config)?;
+    fs::write(&config_path, config_content)
+        .with_context(|| format!("Failed to write language config {:?}", config_path))?;
+
+    info!("Game language set to {:?}", language);
+    Ok(())
+}
+
+pub fn is_language_available(game_path: &Path, language: &str) -> bool {
+    let language_path = get_lang_directory(game_path).join(language);
+    debug!("Checking language directory {:?}", language_path);
+    language_path.is_dir()
+}
+
+/// Selects `language` in the game, remembering the language that was active
+/// before the manager took over so it can be restored on uninstall.
+pub fn select_language(
+    game_path: &Path,
+    metadata: &mut InstalledMetadata,
+    language: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    let current = get_selected_language(game_path)?;
+
+    if current.as_deref() == language {
+        return Ok(());
+    }
+
+    let is_managed =
+        |lang: Option<&str>| lang.is_some_and(|lang| metadata.installed.contains_key(lang));
+
+    if is_managed(language) && !is_managed(current.as_deref()) {
+        debug!("Remembering previous game language {:?}", current);
+        metadata.previous_language = current;
+    }
+
+    set_selected_language(game_path, language)
+}
+
+/// Restores the previously selected language if the game currently points
+/// at `localization_id`, which is about to be (or has been) removed.
+pub fn restore_previous_language(
+    game_path: &Path,
+    metadata: &mut InstalledMetadata,
+    localization_id: &str,
+) -> Result<(), anyhow::Error> {
+    let current = get_selected_language(game_path)?;
+
+    if current.as_deref() != Some(localization_id) {
+        return Ok(());
+    }
+
+    let previous = metadata
+        .previous_language
+        .take()
+        .filter(|lang| lang != localization_id && is_language_available(game_path, lang));
+
+    info!(
+        "Restoring game language {:?} after removing '{}'",
+        previous, localization_id
+    );
+    set_selected_language(game_path, previous.as_deref())
+}
@@ -0,0 +1,363 @@
+use crate::settings::{LaunchMode, LaunchSettings};
+use crate::steam;
+use anyhow::Context;
+use log::info;
+use std::path::Path;
+use std::process::Command;
+
+pub fn launch_game(
+    launch_settings: &LaunchSettings,
+    game_path: &Path,
+) -> Result<(), anyhow::Error> {
+    match launch_settings.mode {
+        LaunchMode::SteamUrl => steam::launch_game(),
+        LaunchMode::SteamApplaunch => {
+            let (steam_program, mut args) = steam::get_steam_command()?;
+            args.push("-applaunch".to_string());
+            args.push(steam::LIMBUS_STEAM_ID.to_string());
+            args.extend(launch_settings.extra_args.iter().cloned());
+
+            spawn(&steam_program, &args, None)
+        }
+        LaunchMode::Direct => {
+            let game_exe = game_path.join("LimbusCompany.exe");
+            if !game_exe.exists() {
+                return Err(anyhow::anyhow!(
+                    "Game executable not found at {:?}",
+                    game_exe
+                ));
+            }
+
+            let mut command_line = match &launch_settings.wrapper_command {
+                Some(wrapper_command) if !wrapper_command.trim().is_empty() => {
+                    split_command_line(wrapper_command)?
+                }
+                _ if cfg!(target_os = "windows") => vec![],
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "A Proton or Wine command is required to run the game directly"
+                    ))
+                }
+            };
+
+            command_line.push(game_exe.to_string_lossy().to_string());
+            command_line.extend(launch_settings.extra_args.iter().cloned());
+
+            let (program, args) = command_line.split_first().unwrap();
+            spawn(Path::new(program), args, Some(game_path))
+        }
+        LaunchMode::Custom => {
+            let template = launch_settings
+                .command_template
+                .as_deref()
+                .filter(|template| !template.trim().is_empty())
+                .ok_or_else(|| anyhow::anyhow!("Custom launch command is not set"))?;
+
+            let command_line: Vec<String> = split_command_line(template)?
+                .iter()
+                .map(|arg| expand_placeholders(arg, game_path))
+                .collect();
+
+            let (program, args) = command_line
+                .split_first()
+                .ok_or_else(|| anyhow::anyhow!("Custom launch command is empty"))?;
+            spawn(Path::new(program), args, Some(game_path))
+        }
+    }
+}
+
+fn spawn(program: &Path, args: &[String], current_dir: Option<&Path>) -> Result<(), anyhow::Error> {
+    info!("Launching {:?} with arguments {:?}", program, args);
+
+    let mut command = Command::new(program);
+    command.args(args);
+
+    if let Some(current_dir) = current_dir {
+        command.current_dir(current_dir);
+    }
+
+    command
+        .spawn()
+        .with_context(|| format!("Failed to launch {:?}", program))?;
+
+    Ok(())
+}
+
+pub fn expand_placeholders(arg: &str, game_path: &Path) -> String {
+    arg.replace("{game_dir}", &game_path.to_string_lossy())
+        .replace(
+            "{game_exe}",
+            &game_path.join("LimbusCompany.exe").to_string_lossy(),
+        )
+        .replace("{app_id}", &steam::LIMBUS_STEAM_ID.to_string())
+}
+
+/// Splits a command line into arguments, honouring single and double quotes.
+/// Backslashes are kept as is so Windows paths need no escaping.
+pub fn split_command_line(command_line: &str) -> Result<Vec<String>, anyhow::Error> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut has_arg = false;
+    let mut quote = None;
+
+    for c in command_line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                has_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(anyhow::anyhow!(
+            "Unterminated quote in command: {}",
+            command_line
+        ));
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn splits_plain_arguments() {
+        assert_eq!(
+            split_command_line("  wine   LimbusCompany.exe -popupwindow ").unwrap(),
+            vec!["wine", "LimbusCompany.exe", "-popupwindow"]
+        );
+        assert!(split_command_line("   ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(
+            split_command_line(r#""C:\Program Files\Proton\proton" run 'it''s' a"b c"d"#).unwrap(),
+            vec![r"C:\Program Files\Proton\proton", "run", "its", "ab cd"]
+        );
+        assert_eq!(
+            split_command_line(r#"echo "it's" 'say "hi"'"#).unwrap(),
+            vec!["echo", "it's", r#"say "hi""#]
+        );
+    }
+
+    #[test]
+    fn keeps_empty_quoted_arguments() {
+        assert_eq!(
+            split_command_line(r#"cmd "" '' x"#).unwrap(),
+            vec!["cmd", "", "", "x"]
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(split_command_line(r#"wine "LimbusCompany.exe"#).is_err());
+        assert!(split_command_line("wine 'LimbusCompany.exe").is_err());
+    }
+
+    #[test]
+    fn expands_placeholders() {
+        let game_path = Path::new("/games/Limbus Company");
+
+        assert_eq!(
+            expand_placeholders("--dir={game_dir}", game_path),
+            "--dir=/games/Limbus Company"
+        );
+        assert_eq!(
+            expand_placeholders("{game_exe}", game_path),
+            game_path
+                .join("LimbusCompany.exe")
+                .to_string_lossy()
+                .to_string()
+        );
+        assert_eq!(
+            expand_placeholders("steam://run/{app_id}/{app_id}", game_path),
+            "steam://run/1973530/1973530"
+        );
+        assert_eq!(expand_placeholders("{unknown}", game_path), "{unknown}");
+    }
+
+    #[test]
+    fn custom_mode_requires_a_command() {
+        let launch_settings = LaunchSettings {
+            mode: LaunchMode::Custom,
+            command_template: Some("  ".to_string()),
+            ..Default::default()
+        };
+
+        assert!(launch_game(&launch_settings, Path::new("/games/Limbus Company")).is_err());
+    }
+
+    /// Writes a script that records its working directory and arguments,
+    /// one per line, to `args.txt` next to itself.
+    #[cfg(unix)]
+    fn write_fake_program(path: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::write(
+            path,
+            "#!/bin/sh\nout=\"$(dirname \"$0\")/args\"\nprintf '%s\\n' \"$PWD\" \"$@\" > \"$out.tmp\" && mv \"$out.tmp\" \"$out.txt\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Waits for the fake program in `dir` to run and returns what it recorded.
+    #[cfg(unix)]
+    fn wait_for_fake_program(dir: &Path) -> Vec<String> {
+        let output_path = dir.join("args.txt");
+
+        let started = Instant::now();
+        while !output_path.exists() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "fake program did not run"
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let output = fs::read_to_string(&output_path).unwrap();
+        output.lines().map(|line| line.to_string()).collect()
+    }
+
+    #[cfg(unix)]
+    fn create_game_dir(root: &Path) -> std::path::PathBuf {
+        let game_path = root.join("Limbus Company");
+        fs::create_dir_all(&game_path).unwrap();
+        fs::write(game_path.join("LimbusCompany.exe"), b"MZ").unwrap();
+        fs::canonicalize(&game_path).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn custom_mode_runs_the_expanded_command() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = create_game_dir(game_dir.path());
+        write_fake_program(&game_path.join("fake launcher.sh"));
+
+        let launch_settings = LaunchSettings {
+            mode: LaunchMode::Custom,
+            command_template: Some(
+                r#""{game_dir}/fake launcher.sh" --exe "{game_exe}" steam://run/{app_id}"#
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        launch_game(&launch_settings, &game_path).unwrap();
+
+        assert_eq!(
+            wait_for_fake_program(&game_path),
+            vec![
+                game_path.to_str().unwrap(),
+                "--exe",
+                game_path.join("LimbusCompany.exe").to_str().unwrap(),
+                "steam://run/1973530",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn direct_mode_runs_the_game_through_the_wrapper() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = create_game_dir(game_dir.path());
+
+        let wrapper_dir = game_dir.path().join("Proton 9.0");
+        fs::create_dir_all(&wrapper_dir).unwrap();
+        write_fake_program(&wrapper_dir.join("proton"));
+
+        let launch_settings = LaunchSettings {
+            mode: LaunchMode::Direct,
+            wrapper_command: Some(format!("\"{}\" run", wrapper_dir.join("proton").display())),
+            extra_args: vec!["-popupwindow".to_string(), "--lang ru".to_string()],
+            ..Default::default()
+        };
+        launch_game(&launch_settings, &game_path).unwrap();
+
+        assert_eq!(
+            wait_for_fake_program(&wrapper_dir),
+            vec![
+                game_path.to_str().unwrap(),
+                "run",
+                game_path.join("LimbusCompany.exe").to_str().unwrap(),
+                "-popupwindow",
+                "--lang ru",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn direct_mode_requires_the_game_executable() {
+        let game_dir = tempfile::tempdir().unwrap();
+
+        let launch_settings = LaunchSettings {
+            mode: LaunchMode::Direct,
+            wrapper_command: Some("wine".to_string()),
+            ..Default::default()
+        };
+        assert!(launch_game(&launch_settings, game_dir.path()).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn steam_applaunch_mode_runs_steam_with_the_app_id() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = create_game_dir(game_dir.path());
+
+        // Without a Steam installation in HOME the native `steam` from PATH is used
+        let home_dir = tempfile::tempdir().unwrap();
+        let bin_dir = tempfile::tempdir().unwrap();
+        write_fake_program(&bin_dir.path().join("steam"));
+
+        let old_path = std::env::var_os("PATH").unwrap_or_default();
+        let old_home = std::env::var_os("HOME");
+        let path = std::env::join_paths(
+            std::iter::once(bin_dir.path().to_path_buf()).chain(std::env::split_paths(&old_path)),
+        )
+        .unwrap();
+        std::env::set_var("PATH", path);
+        std::env::set_var("HOME", home_dir.path());
+
+        let launch_settings = LaunchSettings {
+            mode: LaunchMode::SteamApplaunch,
+            extra_args: vec!["-popupwindow".to_string(), "--lang ru".to_string()],
+            ..Default::default()
+        };
+        let result = launch_game(&launch_settings, &game_path);
+
+        std::env::set_var("PATH", old_path);
+        match old_home {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
+        result.unwrap();
+
+        let recorded = wait_for_fake_program(bin_dir.path());
+        assert_eq!(
+            recorded[1..],
+            ["-applaunch", "1973530", "-popupwindow", "--lang ru"]
+        );
+    }
+}
@@ -1,9 +1,25 @@
+mod archive_cache;
+mod audit_log;
+mod bundle;
+mod cli;
+mod deep_link;
+mod font_cache;
+mod font_validation;
+mod game_watcher;
+mod hooks;
+mod language;
+mod launcher;
+mod metadata_mirror;
+mod queue;
 mod settings;
 mod steam;
 mod utils;
+mod vdf;
 
+use anyhow::Context;
 use dashmap::DashMap;
-use log::{error, info};
+use game_watcher::GameWatcher;
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use tauri::{Emitter, Manager, State};
 use tokio::sync::Mutex;
@@ -12,6 +28,9 @@ use tokio::sync::Mutex;
 struct AppState {
     settings: settings::AppSettings,
     installed_metadata: Option<utils::InstalledMetadata>,
+    game_build: Option<steam::GameBuildInfo>,
+    #[serde(skip)]
+    metadata_mirror: Option<metadata_mirror::MetadataMirror>,
 }
 
 impl AppState {
@@ -22,11 +41,19 @@ impl AppState {
                 settings::AppSettings::default()
             }),
             installed_metadata: None,
+            game_build: None,
+            metadata_mirror: metadata_mirror::MetadataMirror::new(app_handle)
+                .map_err(|e| error!("Failed to resolve metadata mirror directory: {:?}", e))
+                .ok(),
         };
 
+        app_state.settings.ensure_active_profile();
+
         app_state.load_installed_metadata().unwrap_or_else(|e| {
             error!("Failed to load installed metadata: {}", e);
         });
+        app_state.refresh_game_build();
+        app_state.migrate_legacy_font_caches(app_handle);
 
         app_state
     }
@@ -36,8 +63,20 @@ impl AppState {
         app_handle: &tauri::AppHandle,
         new_settings: &settings::AppSettings,
     ) -> anyhow::Result<()> {
+        let mut new_settings = new_settings.clone();
+        new_settings.sync_active_profile();
+
+        if new_settings.font_cache_directory != self.settings.font_cache_directory {
+            let old_font_cache_dir = self.get_font_cache_dir(app_handle)?;
+            let new_font_cache_dir = font_cache::get_font_cache_dir(
+                app_handle,
+                new_settings.font_cache_directory.as_deref(),
+            )?;
+            font_cache::migrate_font_cache(&old_font_cache_dir, &new_font_cache_dir)?;
+        }
+
         settings::save_settings(app_handle, &new_settings)?;
-        self.settings = new_settings.clone();
+        self.settings = new_settings;
         Ok(())
     }
 
@@ -57,33 +96,310 @@ impl AppState {
             steam::get_game_directory()?
         };
 
-        let installed_metadata = utils::load_installed_metadata(&game_path)?;
+        let installed_metadata = self.read_metadata(&game_path)?;
 
         self.installed_metadata = Some(installed_metadata);
         self.settings.game_directory = game_directory.clone();
+        self.settings.sync_active_profile();
+        self.refresh_game_build();
         Ok(())
     }
 
-    fn load_installed_metadata(&mut self) -> anyhow::Result<()> {
-        let game_path = if let Some(game_directory) = &self.settings.game_directory {
-            std::path::PathBuf::from(game_directory)
+    fn get_game_path(&self) -> anyhow::Result<std::path::PathBuf> {
+        if let Some(game_directory) = &self.settings.game_directory {
+            Ok(std::path::PathBuf::from(game_directory))
         } else {
-            steam::get_game_directory()?
-        };
+            steam::get_game_directory()
+        }
+    }
+
+    fn get_profile_game_path(&self, profile_name: &str) -> anyhow::Result<std::path::PathBuf> {
+        let profile = self
+            .settings
+            .profiles
+            .get(profile_name)
+            .ok_or_else(|| anyhow::anyhow!("Profile '{}' not found", profile_name))?;
+
+        if let Some(game_directory) = &profile.game_directory {
+            Ok(std::path::PathBuf::from(game_directory))
+        } else {
+            steam::get_game_directory()
+        }
+    }
+
+    fn is_active_profile(&self, profile_name: &str) -> bool {
+        self.settings.active_profile.as_deref() == Some(profile_name)
+    }
+
+    fn switch_profile(&mut self, profile_name: &str) -> anyhow::Result<()> {
+        if !self.settings.profiles.contains_key(profile_name) {
+            return Err(anyhow::anyhow!("Profile '{}' not found", profile_name));
+        }
+
+        // Nothing changes until the new profile's metadata has been loaded
+        let game_path = self.get_profile_game_path(profile_name)?;
+        let installed_metadata = self.read_metadata(&game_path)?;
 
-        self.installed_metadata = Some(utils::load_installed_metadata(&game_path)?);
+        self.settings.sync_active_profile();
+        self.settings.active_profile = Some(profile_name.to_string());
+        self.settings.apply_active_profile();
+
+        self.installed_metadata = Some(installed_metadata);
+        self.refresh_game_build();
+        self.restore_game_language(profile_name, &game_path);
         Ok(())
     }
 
-    fn save_installed_metadata(&self) -> anyhow::Result<()> {
-        let game_path = if let Some(game_directory) = &self.settings.game_directory {
-            std::path::PathBuf::from(game_directory)
+    /// Stores the language currently selected in the game of `profile_name` on that profile.
+    /// Returns whether the settings changed and need to be saved.
+    fn remember_game_language(&mut self, profile_name: &str, game_path: &std::path::Path) -> bool {
+        let game_language = language::get_selected_language(game_path).unwrap_or_else(|e| {
+            warn!("Failed to read game language: {:?}", e);
+            None
+        });
+
+        match self.settings.profiles.get_mut(profile_name) {
+            Some(profile) if profile.game_language != game_language => {
+                profile.game_language = game_language;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Selects the profile's remembered language again if the game has lost it.
+    fn restore_game_language(&mut self, profile_name: &str, game_path: &std::path::Path) {
+        let Some(game_language) = self
+            .settings
+            .profiles
+            .get(profile_name)
+            .and_then(|profile| profile.game_language.clone())
+        else {
+            return;
+        };
+
+        let selected_language = language::get_selected_language(game_path).unwrap_or(None);
+        if selected_language.as_ref() == Some(&game_language)
+            || !language::is_language_available(game_path, &game_language)
+        {
+            return;
+        }
+
+        let Some(installed_metadata) = self.installed_metadata.as_mut() else {
+            return;
+        };
+
+        info!(
+            "Restoring game language {} of profile '{}'",
+            game_language, profile_name
+        );
+        if let Err(e) =
+            language::select_language(game_path, installed_metadata, Some(&game_language))
+                .and_then(|_| self.save_installed_metadata())
+        {
+            warn!("Failed to restore game language: {:?}", e);
+        }
+    }
+
+    fn get_profile_metadata(&self, profile_name: &str) -> anyhow::Result<utils::InstalledMetadata> {
+        if self.is_active_profile(profile_name) {
+            if let Some(installed_metadata) = &self.installed_metadata {
+                return Ok(installed_metadata.clone());
+            }
+        }
+
+        let game_path = self.get_profile_game_path(profile_name)?;
+        self.read_metadata(&game_path)
+    }
+
+    /// Like `get_profile_metadata`, but never writes to the game directory or the mirror.
+    /// Game directories without metadata fall back to their mirror, then to nothing installed.
+    fn peek_profile_metadata(
+        &self,
+        profile_name: &str,
+    ) -> anyhow::Result<utils::InstalledMetadata> {
+        if self.is_active_profile(profile_name) {
+            if let Some(installed_metadata) = &self.installed_metadata {
+                return Ok(installed_metadata.clone());
+            }
+        }
+
+        let game_path = self.get_profile_game_path(profile_name)?;
+        if let Some(installed_metadata) = utils::peek_installed_metadata(&game_path)? {
+            return Ok(installed_metadata);
+        }
+
+        let mirrored_metadata = match &self.metadata_mirror {
+            Some(mirror) => mirror.load(&game_path)?,
+            None => None,
+        };
+
+        Ok(mirrored_metadata
+            .map(|mirrored_metadata| mirrored_metadata.metadata)
+            .unwrap_or_else(utils::InstalledMetadata::new))
+    }
+
+    /// Applies `update` to the installed metadata of a profile and saves it.
+    /// The active profile's metadata is kept in memory, others are read from disk.
+    fn update_profile_metadata<F>(&mut self, profile_name: &str, update: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&std::path::Path, &mut utils::InstalledMetadata),
+    {
+        let game_path = self.get_profile_game_path(profile_name)?;
+
+        if self.is_active_profile(profile_name) {
+            let installed_metadata = self
+                .installed_metadata
+                .get_or_insert_with(utils::InstalledMetadata::new);
+            update(&game_path, installed_metadata);
+            self.save_installed_metadata()
         } else {
-            steam::get_game_directory()?
+            let mut installed_metadata = self.read_metadata(&game_path)?;
+            update(&game_path, &mut installed_metadata);
+            self.write_metadata(&game_path, &installed_metadata)
+        }
+    }
+
+    fn get_font_cache_dir(
+        &self,
+        app_handle: &tauri::AppHandle,
+    ) -> anyhow::Result<std::path::PathBuf> {
+        font_cache::get_font_cache_dir(app_handle, self.settings.font_cache_directory.as_deref())
+    }
+
+    fn get_archive_cache(
+        &self,
+        app_handle: &tauri::AppHandle,
+    ) -> anyhow::Result<archive_cache::ArchiveCache> {
+        archive_cache::ArchiveCache::new(app_handle, self.settings.archive_cache_max_size)
+    }
+
+    /// Returns the installed metadata of every profile's existing game directory.
+    /// Fails if any profile can't be read, so callers never see a partial list.
+    fn get_all_installations(
+        &self,
+    ) -> anyhow::Result<Vec<(std::path::PathBuf, utils::InstalledMetadata)>> {
+        let mut installations: Vec<(std::path::PathBuf, utils::InstalledMetadata)> = Vec::new();
+
+        for profile_name in self.settings.profiles.keys() {
+            let game_path = self
+                .get_profile_game_path(profile_name)
+                .with_context(|| format!("Failed to read profile '{}'", profile_name))?;
+
+            if !game_path.is_dir() || installations.iter().any(|(path, _)| *path == game_path) {
+                continue;
+            }
+
+            let installed_metadata = self
+                .peek_profile_metadata(profile_name)
+                .with_context(|| format!("Failed to read profile '{}'", profile_name))?;
+            installations.push((game_path, installed_metadata));
+        }
+
+        Ok(installations)
+    }
+
+    /// Lists `profile: localization` pairs of installed localizations from `source`.
+    fn get_source_references(&self, source: &str) -> Vec<(String, String)> {
+        let mut references = Vec::new();
+
+        for profile_name in self.settings.profiles.keys() {
+            match self.get_profile_game_path(profile_name) {
+                Ok(game_path) if game_path.is_dir() => {}
+                _ => continue,
+            }
+
+            let installed_metadata = match self.peek_profile_metadata(profile_name) {
+                Ok(installed_metadata) => installed_metadata,
+                Err(e) => {
+                    warn!("Skipping profile '{}': {:?}", profile_name, e);
+                    continue;
+                }
+            };
+
+            for installed in installed_metadata.installed.values() {
+                if installed.source == source {
+                    references.push((profile_name.clone(), installed.id.clone()));
+                }
+            }
+        }
+
+        references.sort();
+        references
+    }
+
+    /// Moves per-game `FontCache` folders left by older versions into the shared cache.
+    fn migrate_legacy_font_caches(&self, app_handle: &tauri::AppHandle) {
+        let font_cache_dir = match self.get_font_cache_dir(app_handle) {
+            Ok(font_cache_dir) => font_cache_dir,
+            Err(e) => {
+                error!("Failed to resolve font cache directory: {:?}", e);
+                return;
+            }
         };
 
+        for profile_name in self.settings.profiles.keys() {
+            let Ok(game_path) = self.get_profile_game_path(profile_name) else {
+                continue;
+            };
+
+            let legacy_dir = font_cache::get_legacy_font_cache_dir(&game_path);
+            if let Err(e) = font_cache::migrate_font_cache(&legacy_dir, &font_cache_dir) {
+                error!("Failed to migrate font cache {:?}: {:?}", legacy_dir, e);
+            }
+        }
+    }
+
+    fn refresh_game_build(&mut self) {
+        self.game_build = self
+            .get_game_path()
+            .and_then(|game_path| steam::get_game_build_info(&game_path))
+            .unwrap_or_else(|e| {
+                warn!("Failed to read game build info: {:?}", e);
+                None
+            });
+    }
+
+    fn load_installed_metadata(&mut self) -> anyhow::Result<()> {
+        let game_path = self.get_game_path()?;
+
+        self.installed_metadata = Some(self.read_metadata(&game_path)?);
+        Ok(())
+    }
+
+    fn save_installed_metadata(&self) -> anyhow::Result<()> {
+        let game_path = self.get_game_path()?;
+
         if let Some(metadata) = &self.installed_metadata {
-            utils::save_installed_metadata(&game_path, metadata)?;
+            self.write_metadata(&game_path, metadata)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the installed metadata of `game_path`, restoring it from the mirror
+    /// when the game directory has lost its copy.
+    fn read_metadata(
+        &self,
+        game_path: &std::path::Path,
+    ) -> anyhow::Result<utils::InstalledMetadata> {
+        match &self.metadata_mirror {
+            Some(mirror) => mirror.load_installed_metadata(game_path),
+            None => utils::load_installed_metadata(&game_path.to_path_buf()),
+        }
+    }
+
+    fn write_metadata(
+        &self,
+        game_path: &std::path::Path,
+        installed_metadata: &utils::InstalledMetadata,
+    ) -> anyhow::Result<()> {
+        utils::save_installed_metadata(&game_path.to_path_buf(), installed_metadata)?;
+
+        if let Some(mirror) = &self.metadata_mirror {
+            mirror
+                .save(game_path, installed_metadata)
+                .unwrap_or_else(|e| warn!("Failed to mirror installed metadata: {:?}", e));
         }
 
         Ok(())
@@ -99,6 +415,9 @@ struct RemoteLocalizations {
 type AppStateMutex = Mutex<AppState>;
 type RemoteLocalizationsMutex = Mutex<Option<RemoteLocalizations>>;
 type LocalizationLocks = DashMap<(String, std::path::PathBuf), Mutex<()>>;
+type FontCacheLock = Mutex<()>; // Held while fonts are added to or pruned from the cache
+type OperationQueueMutex = Mutex<queue::OperationQueue>;
+type OperationQueueNotify = tokio::sync::Notify;
 
 #[tauri::command]
 async fn get_latest_version() -> Result<String, String> {
@@ -154,6 +473,11 @@ async fn get_available_localizations(
     Ok(localizations)
 }
 
+#[tauri::command]
+async fn is_game_running(game_watcher: State<'_, GameWatcher>) -> Result<bool, String> {
+    Ok(game_watcher.is_running())
+}
+
 #[tauri::command]
 async fn get_app_state(state: State<'_, AppStateMutex>) -> Result<AppState, String> {
     let app_state_guard = state.lock().await;
@@ -174,15 +498,1617 @@ async fn update_settings(
         *remote_localizations_guard = None;
     }
 
-    app_state_guard
-        .update_settings(&app_handle, &new_settings)
+    app_state_guard
+        .update_settings(&app_handle, &new_settings)
+        .map_err(|e| {
+            error!("Failed to update settings: {:?}", e);
+            e.to_string()
+        })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+async fn install_localization_files(
+    localization_lock: &LocalizationLocks,
+    font_cache_lock: &FontCacheLock,
+    game_path: &std::path::PathBuf,
+    archive_cache: &archive_cache::ArchiveCache,
+    font_cache_dir: &std::path::Path,
+    localization: &utils::Localization,
+) -> Result<(), String> {
+    let lock = localization_lock
+        .entry((localization.id.clone(), game_path.clone()))
+        .or_insert_with(|| Mutex::new(()));
+    let _aquired_lock = lock.lock().await;
+    let _font_cache_guard = font_cache_lock.lock().await;
+
+    utils::install_localization(game_path, archive_cache, font_cache_dir, localization)
+        .await
+        .map_err(|e| {
+            error!("Failed to install localization: {:?}", e);
+            e.to_string()
+        })?;
+
+    utils::install_fonts_for_localization(game_path, font_cache_dir, localization)
+        .await
+        .map_err(|e| {
+            error!("Failed to install fonts for localization: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+fn record_audit_event(
+    app_handle: &tauri::AppHandle,
+    mut event: audit_log::AuditEvent,
+    started: std::time::Instant,
+    result: &Result<(), String>,
+) {
+    event.finish(started, result);
+    audit_log::append_event(app_handle, &event).unwrap_or_else(|e| {
+        error!("Failed to write audit log: {:?}", e);
+    });
+}
+
+/// Installs a localization into the game directory of `profile_name`
+/// and records it in that profile's metadata and the audit log.
+/// An install over an older version is logged as an update.
+async fn install_to_profile(
+    app_handle: &tauri::AppHandle,
+    profile_name: &str,
+    localization: &utils::Localization,
+    source: &str,
+    select: bool,
+    force: bool,
+    action: audit_log::AuditAction,
+) -> Result<(), String> {
+    let mut event = audit_log::AuditEvent::new(action, Some(profile_name), &localization.id);
+    event.new_version = Some(localization.version.clone());
+    event.source = Some(source.to_string());
+
+    let started = std::time::Instant::now();
+    let result = install_files_to_profile(
+        app_handle,
+        profile_name,
+        localization,
+        source,
+        select,
+        force,
+        &mut event,
+    )
+    .await;
+
+    record_audit_event(app_handle, event, started, &result);
+    result
+}
+
+async fn install_files_to_profile(
+    app_handle: &tauri::AppHandle,
+    profile_name: &str,
+    localization: &utils::Localization,
+    source: &str,
+    select: bool,
+    force: bool,
+    event: &mut audit_log::AuditEvent,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
+    let localization_lock = app_handle.state::<LocalizationLocks>();
+    let font_cache_lock = app_handle.state::<FontCacheLock>();
+
+    let game_path;
+    let font_cache_dir;
+    let archive_cache;
+    let mut is_same_version = false;
+
+    {
+        let app_state_guard = state.lock().await;
+
+        game_path = app_state_guard
+            .get_profile_game_path(profile_name)
+            .map_err(|e| {
+                error!("Failed to get game directory: {:?}", e);
+                e.to_string()
+            })?;
+
+        font_cache_dir = app_state_guard
+            .get_font_cache_dir(app_handle)
+            .map_err(|e| {
+                error!("Failed to get font cache directory: {:?}", e);
+                e.to_string()
+            })?;
+
+        archive_cache = app_state_guard.get_archive_cache(app_handle).map_err(|e| {
+            error!("Failed to get archive cache: {:?}", e);
+            e.to_string()
+        })?;
+
+        let previous = app_state_guard
+            .get_profile_metadata(profile_name)
+            .map_err(|e| {
+                error!("Failed to load installed metadata: {:?}", e);
+                e.to_string()
+            })?
+            .installed
+            .remove(&localization.id);
+
+        if let Some(previous) = previous {
+            is_same_version = previous.version == localization.version;
+            if event.action == audit_log::AuditAction::Install
+                && previous.version != localization.version
+            {
+                event.action = audit_log::AuditAction::Update;
+            }
+            event.old_version = Some(previous.version);
+        }
+    }
+
+    let game_build = steam::get_game_build_info(&game_path).unwrap_or_else(|e| {
+        warn!("Failed to read game build info: {:?}", e);
+        None
+    });
+
+    if let Some(game_build) = game_build.as_ref().filter(|b| b.update_in_progress) {
+        warn!("Game update is in progress, installing anyway");
+        app_handle
+            .emit("game_update_in_progress", game_build)
+            .unwrap();
+    }
+
+    let game_build_id = game_build.and_then(|game_build| game_build.build_id);
+    event.game_build = game_build_id;
+
+    // Repairing the installed version doesn't change what the game loads,
+    // even if that version was force-installed on an incompatible build
+    let is_repair = event.action == audit_log::AuditAction::Repair && is_same_version;
+
+    if let Err(e) = localization.check_compatibility(game_build_id) {
+        if !force && !is_repair {
+            error!("Refusing to install localization: {:?}", e);
+            return Err(e.to_string());
+        }
+        warn!("{}, installing anyway", e);
+    }
+
+    install_localization_files(
+        &localization_lock,
+        &font_cache_lock,
+        &game_path,
+        &archive_cache,
+        &font_cache_dir,
+        localization,
+    )
+    .await?;
+
+    let mut app_state_guard = state.lock().await;
+
+    app_state_guard
+        .update_profile_metadata(profile_name, |game_path, installed_metadata| {
+            installed_metadata.add_localization(localization, source, game_build_id);
+
+            if select {
+                language::select_language(game_path, installed_metadata, Some(&localization.id))
+                    .unwrap_or_else(|e| {
+                        error!("Failed to select game language: {:?}", e);
+                    });
+            }
+        })
+        .map_err(|e| {
+            error!("Failed to save installed metadata: {:?}", e);
+            e.to_string()
+        })?;
+
+    if select && app_state_guard.remember_game_language(profile_name, &game_path) {
+        app_state_guard
+            .save_settings(app_handle)
+            .unwrap_or_else(|e| error!("Failed to save settings: {:?}", e));
+    }
+
+    if app_state_guard.is_active_profile(profile_name) {
+        app_state_guard.refresh_game_build();
+    }
+
+    Ok(())
+}
+
+/// Reinstalls the version that the last update of a localization replaced.
+async fn rollback_in_profile(
+    app_handle: &tauri::AppHandle,
+    profile_name: &str,
+    localization_id: &str,
+    force: bool,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
+
+    let installed = state
+        .lock()
+        .await
+        .peek_profile_metadata(profile_name)
+        .map_err(|e| {
+            error!("Failed to load installed metadata: {:?}", e);
+            e.to_string()
+        })?
+        .installed
+        .remove(localization_id)
+        .ok_or_else(|| format!("Localization '{}' is not installed", localization_id))?;
+
+    let previous = installed.previous.ok_or_else(|| {
+        format!(
+            "No previous version of localization '{}' to roll back to",
+            localization_id
+        )
+    })?;
+
+    install_to_profile(
+        app_handle,
+        profile_name,
+        &previous,
+        &installed.source,
+        false,
+        force,
+        audit_log::AuditAction::Rollback,
+    )
+    .await
+}
+
+/// Removes a localization from the game directory of `profile_name`
+/// and from that profile's metadata.
+async fn uninstall_from_profile(
+    app_handle: &tauri::AppHandle,
+    profile_name: &str,
+    localization: &utils::Localization,
+) -> Result<(), String> {
+    let mut event = audit_log::AuditEvent::new(
+        audit_log::AuditAction::Uninstall,
+        Some(profile_name),
+        &localization.id,
+    );
+
+    let started = std::time::Instant::now();
+    let result =
+        uninstall_files_from_profile(app_handle, profile_name, localization, &mut event).await;
+
+    record_audit_event(app_handle, event, started, &result);
+    result
+}
+
+async fn uninstall_files_from_profile(
+    app_handle: &tauri::AppHandle,
+    profile_name: &str,
+    localization: &utils::Localization,
+    event: &mut audit_log::AuditEvent,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
+    let localization_lock = app_handle.state::<LocalizationLocks>();
+
+    let game_path;
+
+    {
+        let app_state_guard = state.lock().await;
+
+        game_path = app_state_guard
+            .get_profile_game_path(profile_name)
+            .map_err(|e| {
+                error!("Failed to get game directory: {:?}", e);
+                e.to_string()
+            })?;
+
+        if let Some(installed) = app_state_guard
+            .get_profile_metadata(profile_name)
+            .ok()
+            .and_then(|mut installed_metadata| {
+                installed_metadata.installed.remove(&localization.id)
+            })
+        {
+            event.old_version = Some(installed.version);
+            event.source = Some(installed.source);
+        }
+    }
+
+    event.game_build = steam::get_game_build_info(&game_path)
+        .ok()
+        .flatten()
+        .and_then(|game_build| game_build.build_id);
+
+    let lock = localization_lock
+        .entry((localization.id.clone(), game_path.clone()))
+        .or_insert_with(|| Mutex::new(()));
+    let _aquired_lock = lock.lock().await;
+
+    utils::uninstall_localization(&game_path, localization)
+        .await
+        .map_err(|e| {
+            error!("Failed to uninstall localization: {:?}", e);
+            e.to_string()
+        })?;
+
+    let mut app_state_guard = state.lock().await;
+
+    app_state_guard
+        .update_profile_metadata(profile_name, |game_path, installed_metadata| {
+            installed_metadata.installed.remove(&localization.id);
+
+            language::restore_previous_language(game_path, installed_metadata, &localization.id)
+                .unwrap_or_else(|e| {
+                    error!("Failed to restore game language: {:?}", e);
+                });
+        })
+        .map_err(|e| {
+            error!("Failed to save installed metadata: {:?}", e);
+            e.to_string()
+        })?;
+
+    if app_state_guard.remember_game_language(profile_name, &game_path) {
+        app_state_guard
+            .save_settings(app_handle)
+            .unwrap_or_else(|e| error!("Failed to save settings: {:?}", e));
+    }
+    drop(app_state_guard);
+
+    if let Err(e) = prune_unreferenced_fonts(app_handle, &state).await {
+        error!("Failed to prune font cache: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Resolves the shared font cache and the fonts referenced by all profiles.
+async fn get_font_cache_references(
+    app_handle: &tauri::AppHandle,
+    state: &AppStateMutex,
+) -> Result<(std::path::PathBuf, font_cache::FontReferences), String> {
+    let app_state_guard = state.lock().await;
+
+    let font_cache_dir = app_state_guard
+        .get_font_cache_dir(app_handle)
+        .map_err(|e| {
+            error!("Failed to get font cache directory: {:?}", e);
+            e.to_string()
+        })?;
+
+    // Fonts of a profile that can't be read would look unreferenced
+    let installations = app_state_guard.get_all_installations().map_err(|e| {
+        error!("Failed to collect font references: {:?}", e);
+        e.to_string()
+    })?;
+    drop(app_state_guard);
+
+    Ok((
+        font_cache_dir,
+        font_cache::collect_font_references(&installations),
+    ))
+}
+
+/// Removes unreferenced fonts while no install can add new ones to the cache.
+async fn prune_unreferenced_fonts(
+    app_handle: &tauri::AppHandle,
+    state: &AppStateMutex,
+) -> Result<font_cache::PruneResult, String> {
+    let font_cache_lock = app_handle.state::<FontCacheLock>();
+    let _font_cache_guard = font_cache_lock.lock().await;
+
+    let (font_cache_dir, references) = get_font_cache_references(app_handle, state).await?;
+
+    font_cache::prune_font_cache(&font_cache_dir, &references).map_err(|e| {
+        error!("Failed to prune font cache: {:?}", e);
+        e.to_string()
+    })
+}
+
+async fn get_active_profile(state: &AppStateMutex) -> Result<(String, Option<String>), String> {
+    let app_state_guard = state.lock().await;
+
+    let profile_name = app_state_guard
+        .settings
+        .active_profile
+        .clone()
+        .ok_or_else(|| "No active profile selected".to_string())?;
+
+    Ok((
+        profile_name,
+        app_state_guard.settings.selected_source.clone(),
+    ))
+}
+
+async fn enqueue_operation(
+    app_handle: &tauri::AppHandle,
+    operation: queue::Operation,
+) -> Result<(), String> {
+    let operation_queue = app_handle.state::<OperationQueueMutex>();
+    let mut operation_queue_guard = operation_queue.lock().await;
+
+    let id = operation_queue_guard.push(operation);
+    info!("Queued operation {}", id);
+
+    queue::save_queue(app_handle, &operation_queue_guard).map_err(|e| {
+        error!("Failed to save operation queue: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("operation_queue_updated", &operation_queue_guard.operations)
+        .map_err(|e| {
+            error!("Failed to emit operation queue updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    app_handle.state::<OperationQueueNotify>().notify_one();
+    Ok(())
+}
+
+async fn run_operation(
+    app_handle: &tauri::AppHandle,
+    operation: &queue::Operation,
+) -> Result<(), String> {
+    match operation {
+        queue::Operation::Install {
+            profile,
+            source,
+            localization,
+            select,
+            force,
+        } => {
+            install_to_profile(
+                app_handle,
+                profile,
+                localization,
+                source,
+                *select,
+                *force,
+                audit_log::AuditAction::Install,
+            )
+            .await
+        }
+        queue::Operation::Repair {
+            profile,
+            source,
+            localization,
+        } => {
+            install_to_profile(
+                app_handle,
+                profile,
+                localization,
+                source,
+                false,
+                false,
+                audit_log::AuditAction::Repair,
+            )
+            .await
+        }
+        queue::Operation::Uninstall {
+            profile,
+            localization,
+        } => uninstall_from_profile(app_handle, profile, localization).await,
+        queue::Operation::Rollback {
+            profile,
+            localization_id,
+            force,
+        } => rollback_in_profile(app_handle, profile, localization_id, *force).await,
+        queue::Operation::SetLanguage { profile, language } => {
+            set_profile_language(app_handle, profile, language.as_deref()).await
+        }
+    }
+}
+
+/// Runs queued operations one by one until the queue is empty
+/// or the game is started again.
+async fn process_operation_queue(app_handle: &tauri::AppHandle) {
+    let game_watcher = app_handle.state::<GameWatcher>();
+    let operation_queue = app_handle.state::<OperationQueueMutex>();
+
+    while !game_watcher.is_running() {
+        let queued_operation = {
+            let mut operation_queue_guard = operation_queue.lock().await;
+
+            if !operation_queue_guard.has_pending() {
+                break;
+            }
+
+            let queued_operation = operation_queue_guard.start_next();
+            app_handle
+                .emit("operation_queue_updated", &operation_queue_guard.operations)
+                .unwrap();
+            queued_operation
+        };
+
+        let Some(queued_operation) = queued_operation else {
+            break;
+        };
+
+        info!("Running queued operation {}", queued_operation.id);
+        let result = run_operation(app_handle, &queued_operation.operation).await;
+
+        let mut operation_queue_guard = operation_queue.lock().await;
+        match result {
+            Ok(()) => operation_queue_guard.complete(queued_operation.id),
+            Err(e) => {
+                error!("Queued operation {} failed: {}", queued_operation.id, e);
+                operation_queue_guard.fail(queued_operation.id, e);
+            }
+        }
+
+        queue::save_queue(app_handle, &operation_queue_guard).unwrap_or_else(|e| {
+            error!("Failed to save operation queue: {:?}", e);
+        });
+
+        app_handle
+            .emit("operation_queue_updated", &operation_queue_guard.operations)
+            .unwrap();
+
+        let app_state_guard = app_handle.state::<AppStateMutex>().lock().await.clone();
+        app_handle
+            .emit("app_state_updated", app_state_guard)
+            .unwrap();
+    }
+}
+
+async fn run_post_exit_hooks(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<AppStateMutex>();
+    let (post_exit_hooks, game_path) = {
+        let app_state_guard = state.lock().await;
+        (
+            app_state_guard.settings.hooks.post_exit.clone(),
+            app_state_guard.get_game_path(),
+        )
+    };
+
+    if post_exit_hooks.is_empty() {
+        return;
+    }
+
+    let game_path = match game_path {
+        Ok(game_path) => game_path,
+        Err(e) => {
+            error!("Failed to get game directory for post-exit hooks: {:?}", e);
+            return;
+        }
+    };
+
+    hooks::run_hooks(
+        app_handle,
+        hooks::HookStage::PostExit,
+        &post_exit_hooks,
+        &game_path,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        error!("Post-exit hook failed: {:?}", e);
+    });
+}
+
+/// Reacts to the game process state: runs post-exit hooks when the game
+/// closes and then applies queued operations while it is not running.
+async fn watch_game_sessions(app_handle: tauri::AppHandle) {
+    let mut game_running = app_handle.state::<GameWatcher>().subscribe();
+    let operation_queue_notify = app_handle.state::<OperationQueueNotify>();
+    let mut was_running = *game_running.borrow();
+
+    loop {
+        let is_running = *game_running.borrow_and_update();
+
+        if was_running && !is_running {
+            run_post_exit_hooks(&app_handle).await;
+        }
+        was_running = is_running;
+
+        if !is_running {
+            process_operation_queue(&app_handle).await;
+        }
+
+        tokio::select! {
+            changed = game_running.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            _ = operation_queue_notify.notified() => {}
+        }
+    }
+}
+
+#[tauri::command]
+async fn install_localization(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    game_watcher: State<'_, GameWatcher>,
+    localization: utils::Localization,
+    select: Option<bool>,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let (profile, source) = get_active_profile(&state).await?;
+    let source = source.ok_or_else(|| "No active source selected".to_string())?;
+    let select = select.unwrap_or(true);
+    let force = force.unwrap_or(false);
+
+    if game_watcher.is_running() {
+        return enqueue_operation(
+            &app_handle,
+            queue::Operation::Install {
+                profile,
+                source,
+                localization,
+                select,
+                force,
+            },
+        )
+        .await;
+    }
+
+    install_to_profile(
+        &app_handle,
+        &profile,
+        &localization,
+        &source,
+        select,
+        force,
+        audit_log::AuditAction::Install,
+    )
+    .await?;
+
+    let app_state_guard = state.lock().await;
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn uninstall_localization(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    game_watcher: State<'_, GameWatcher>,
+    localization: utils::Localization,
+) -> Result<(), String> {
+    let (profile, _) = get_active_profile(&state).await?;
+
+    if game_watcher.is_running() {
+        return enqueue_operation(
+            &app_handle,
+            queue::Operation::Uninstall {
+                profile,
+                localization,
+            },
+        )
+        .await;
+    }
+
+    uninstall_from_profile(&app_handle, &profile, &localization).await?;
+
+    let app_state_guard = state.lock().await;
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn repair_localization(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    game_watcher: State<'_, GameWatcher>,
+    localization: utils::Localization,
+) -> Result<(), String> {
+    let (profile, source) = get_active_profile(&state).await?;
+    let source = source.ok_or_else(|| "No active source selected".to_string())?;
+
+    if game_watcher.is_running() {
+        return enqueue_operation(
+            &app_handle,
+            queue::Operation::Repair {
+                profile,
+                source,
+                localization,
+            },
+        )
+        .await;
+    }
+
+    install_to_profile(
+        &app_handle,
+        &profile,
+        &localization,
+        &source,
+        false,
+        false,
+        audit_log::AuditAction::Repair,
+    )
+    .await?;
+
+    let app_state_guard = state.lock().await;
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn rollback_localization(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    game_watcher: State<'_, GameWatcher>,
+    localization_id: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let (profile, _) = get_active_profile(&state).await?;
+    let force = force.unwrap_or(false);
+
+    if game_watcher.is_running() {
+        return enqueue_operation(
+            &app_handle,
+            queue::Operation::Rollback {
+                profile,
+                localization_id,
+                force,
+            },
+        )
+        .await;
+    }
+
+    rollback_in_profile(&app_handle, &profile, &localization_id, force).await?;
+
+    let app_state_guard = state.lock().await;
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_operation_queue(
+    operation_queue: State<'_, OperationQueueMutex>,
+) -> Result<Vec<queue::QueuedOperation>, String> {
+    Ok(operation_queue.lock().await.operations.clone())
+}
+
+#[tauri::command]
+async fn remove_queued_operation(
+    app_handle: tauri::AppHandle,
+    operation_queue: State<'_, OperationQueueMutex>,
+    id: u64,
+) -> Result<(), String> {
+    let mut operation_queue_guard = operation_queue.lock().await;
+
+    if !operation_queue_guard.remove(id) {
+        return Err(format!("Operation {} not found or already running", id));
+    }
+
+    queue::save_queue(&app_handle, &operation_queue_guard).map_err(|e| {
+        error!("Failed to save operation queue: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("operation_queue_updated", &operation_queue_guard.operations)
+        .map_err(|e| {
+            error!("Failed to emit operation queue updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn retry_queued_operation(
+    app_handle: tauri::AppHandle,
+    operation_queue: State<'_, OperationQueueMutex>,
+    operation_queue_notify: State<'_, OperationQueueNotify>,
+    id: u64,
+) -> Result<(), String> {
+    let mut operation_queue_guard = operation_queue.lock().await;
+
+    if !operation_queue_guard.retry(id) {
+        return Err(format!("Operation {} not found or has not failed", id));
+    }
+
+    queue::save_queue(&app_handle, &operation_queue_guard).map_err(|e| {
+        error!("Failed to save operation queue: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("operation_queue_updated", &operation_queue_guard.operations)
+        .map_err(|e| {
+            error!("Failed to emit operation queue updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    operation_queue_notify.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_game_directory(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    directory: Option<String>,
+) -> Result<(), String> {
+    let mut app_state_guard = state.lock().await;
+
+    app_state_guard
+        .update_game_directory(&directory)
+        .map_err(|e| {
+            error!("Failed to update game directory: {:?}", e);
+            e.to_string()
+        })?;
+    app_state_guard.migrate_legacy_font_caches(&app_handle);
+
+    app_state_guard.save_settings(&app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn install_localization_to_profiles(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    game_watcher: State<'_, GameWatcher>,
+    localization: utils::Localization,
+    profiles: Vec<String>,
+    select: Option<bool>,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let select = select.unwrap_or(true);
+    let force = force.unwrap_or(false);
+
+    let source;
+    let mut targets: Vec<(String, std::path::PathBuf)> = Vec::new();
+
+    {
+        let app_state_guard = state.lock().await;
+
+        source = app_state_guard
+            .settings
+            .selected_source
+            .clone()
+            .ok_or_else(|| "No active source selected".to_string())?;
+
+        for profile_name in &profiles {
+            let game_path = app_state_guard
+                .get_profile_game_path(profile_name)
+                .map_err(|e| {
+                    error!("Failed to get game directory: {:?}", e);
+                    e.to_string()
+                })?;
+
+            if targets.iter().any(|(_, path)| path == &game_path) {
+                info!(
+                    "Skipping profile {} with duplicate game directory",
+                    profile_name
+                );
+                continue;
+            }
+
+            let game_build_id = steam::get_game_build_info(&game_path)
+                .unwrap_or_else(|e| {
+                    warn!("Failed to read game build info: {:?}", e);
+                    None
+                })
+                .and_then(|game_build| game_build.build_id);
+
+            if let Err(e) = localization.check_compatibility(game_build_id) {
+                if !force {
+                    error!("Refusing to install localization: {:?}", e);
+                    return Err(format!("{} (profile '{}')", e, profile_name));
+                }
+            }
+
+            targets.push((profile_name.clone(), game_path));
+        }
+    }
+
+    for (profile_name, _) in targets {
+        if game_watcher.is_running() {
+            enqueue_operation(
+                &app_handle,
+                queue::Operation::Install {
+                    profile: profile_name,
+                    source: source.clone(),
+                    localization: localization.clone(),
+                    select,
+                    force,
+                },
+            )
+            .await?;
+            continue;
+        }
+
+        info!(
+            "Installing localization {} into profile {}",
+            &localization.id, &profile_name
+        );
+
+        install_to_profile(
+            &app_handle,
+            &profile_name,
+            &localization,
+            &source,
+            select,
+            force,
+            audit_log::AuditAction::Install,
+        )
+        .await?;
+    }
+
+    let app_state_guard = state.lock().await;
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn add_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    name: String,
+    game_directory: Option<String>,
+) -> Result<(), String> {
+    let mut app_state_guard = state.lock().await;
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+
+    if app_state_guard.settings.profiles.contains_key(&name) {
+        return Err(format!("Profile '{}' already exists", name));
+    }
+
+    if let Some(game_directory) = &game_directory {
+        steam::validate_game_directory(game_directory).map_err(|e| {
+            error!("Failed to validate game directory: {:?}", e);
+            e.to_string()
+        })?;
+    }
+
+    app_state_guard.settings.sync_active_profile();
+
+    if app_state_guard
+        .settings
+        .profiles
+        .values()
+        .any(|profile| profile.game_directory == game_directory)
+    {
+        return Err("Another profile already uses this game directory".to_string());
+    }
+
+    let selected_source = app_state_guard.settings.selected_source.clone();
+    app_state_guard.settings.profiles.insert(
+        name,
+        settings::GameProfile {
+            game_directory,
+            selected_source,
+            game_language: None,
+        },
+    );
+    app_state_guard.migrate_legacy_font_caches(&app_handle);
+
+    app_state_guard.save_settings(&app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn remove_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    name: String,
+) -> Result<(), String> {
+    let mut app_state_guard = state.lock().await;
+
+    if app_state_guard.is_active_profile(&name) {
+        return Err("Cannot remove the active profile".to_string());
+    }
+
+    if app_state_guard.settings.profiles.remove(&name).is_none() {
+        return Err(format!("Profile '{}' not found", name));
+    }
+
+    app_state_guard.save_settings(&app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn switch_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    remote_localizations: State<'_, RemoteLocalizationsMutex>,
+    name: String,
+) -> Result<(), String> {
+    let mut app_state_guard = state.lock().await;
+    let previous_source = app_state_guard.settings.selected_source.clone();
+
+    app_state_guard.switch_profile(&name).map_err(|e| {
+        error!("Failed to switch profile: {:?}", e);
+        e.to_string()
+    })?;
+
+    if app_state_guard.settings.selected_source != previous_source {
+        let mut remote_localizations_guard = remote_localizations.lock().await;
+        *remote_localizations_guard = None;
+    }
+
+    app_state_guard.save_settings(&app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_font_cache(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+) -> Result<font_cache::FontCacheReport, String> {
+    let (font_cache_dir, references) = get_font_cache_references(&app_handle, &state).await?;
+
+    font_cache::list_cached_fonts(&font_cache_dir, &references).map_err(|e| {
+        error!("Failed to list font cache: {:?}", e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+async fn prune_font_cache(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+) -> Result<font_cache::PruneResult, String> {
+    prune_unreferenced_fonts(&app_handle, &state).await
+}
+
+/// Operations that changed the active profile's localizations, newest first.
+#[tauri::command]
+async fn get_install_history(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+) -> Result<Vec<audit_log::AuditEvent>, String> {
+    let (profile_name, _) = get_active_profile(&state).await?;
+
+    let filter = audit_log::AuditFilter {
+        profile: Some(profile_name),
+        succeeded: Some(true),
+        ..Default::default()
+    };
+
+    audit_log::query_events(&app_handle, &filter).map_err(|e| {
+        error!("Failed to query audit log: {:?}", e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+async fn query_audit_log(
+    app_handle: tauri::AppHandle,
+    filter: Option<audit_log::AuditFilter>,
+) -> Result<Vec<audit_log::AuditEvent>, String> {
+    audit_log::query_events(&app_handle, &filter.unwrap_or_default()).map_err(|e| {
+        error!("Failed to query audit log: {:?}", e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+async fn test_source(url: String) -> Result<utils::SourceTestResult, String> {
+    utils::test_source(&url).await.map_err(|e| {
+        error!("Failed to test source: {:?}", e);
+        format!("{:#}", e)
+    })
+}
+
+/// Validates and tests a new source, then adds it to the settings.
+async fn add_source_to_settings(
+    app_handle: &tauri::AppHandle,
+    key: &str,
+    name: &str,
+    url: &str,
+) -> Result<utils::SourceTestResult, String> {
+    let state = app_handle.state::<AppStateMutex>();
+
+    let key = key.trim().to_string();
+    let name = name.trim().to_string();
+    let url = url.trim().to_string();
+
+    if key.is_empty() {
+        return Err("Source key is empty".to_string());
+    }
+
+    if name.is_empty() {
+        return Err("Source name is empty".to_string());
+    }
+
+    if state.lock().await.settings.sources.contains_key(&key) {
+        return Err(format!("Source '{}' already exists", key));
+    }
+
+    let test_result = utils::test_source(&url).await.map_err(|e| {
+        error!("Failed to test source: {:?}", e);
+        format!("{:#}", e)
+    })?;
+
+    let mut app_state_guard = state.lock().await;
+
+    if app_state_guard.settings.sources.contains_key(&key) {
+        return Err(format!("Source '{}' already exists", key));
+    }
+
+    app_state_guard
+        .settings
+        .sources
+        .insert(key, settings::LocalizationSource { name, url });
+
+    app_state_guard.save_settings(app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(test_result)
+}
+
+#[tauri::command]
+async fn add_source(
+    app_handle: tauri::AppHandle,
+    key: String,
+    name: String,
+    url: String,
+) -> Result<utils::SourceTestResult, String> {
+    add_source_to_settings(&app_handle, &key, &name, &url).await
+}
+
+#[tauri::command]
+async fn rename_source(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    key: String,
+    name: String,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Source name is empty".to_string());
+    }
+
+    let mut app_state_guard = state.lock().await;
+
+    app_state_guard
+        .settings
+        .sources
+        .get_mut(&key)
+        .ok_or_else(|| format!("Source '{}' not found", key))?
+        .name = name;
+
+    app_state_guard.save_settings(&app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+/// Removes a source. Installed localizations and profiles using it are moved
+/// to `reassign_to`; without it, removing a source that is still used fails.
+#[tauri::command]
+async fn remove_source(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    remote_localizations: State<'_, RemoteLocalizationsMutex>,
+    key: String,
+    reassign_to: Option<String>,
+) -> Result<(), String> {
+    let mut app_state_guard = state.lock().await;
+
+    if !app_state_guard.settings.sources.contains_key(&key) {
+        return Err(format!("Source '{}' not found", key));
+    }
+
+    if let Some(reassign_to) = &reassign_to {
+        if *reassign_to == key || !app_state_guard.settings.sources.contains_key(reassign_to) {
+            return Err(format!("Cannot reassign to source '{}'", reassign_to));
+        }
+    }
+
+    let references = app_state_guard.get_source_references(&key);
+
+    match &reassign_to {
+        None if !references.is_empty() => {
+            let references: Vec<String> = references
+                .iter()
+                .map(|(profile, localization)| format!("{}: {}", profile, localization))
+                .collect();
+
+            return Err(format!(
+                "Source '{}' is used by installed localizations ({})",
+                key,
+                references.join(", ")
+            ));
+        }
+        None => {}
+        Some(reassign_to) => {
+            for (profile_name, localization_id) in &references {
+                app_state_guard
+                    .update_profile_metadata(profile_name, |_, installed_metadata| {
+                        if let Some(installed) =
+                            installed_metadata.installed.get_mut(localization_id)
+                        {
+                            installed.source = reassign_to.clone();
+                        }
+                    })
+                    .map_err(|e| {
+                        error!("Failed to save installed metadata: {:?}", e);
+                        e.to_string()
+                    })?;
+            }
+        }
+    }
+
+    app_state_guard
+        .settings
+        .replace_source_references(&key, reassign_to.as_deref());
+    app_state_guard.settings.sources.remove(&key);
+
+    let mut remote_localizations_guard = remote_localizations.lock().await;
+    if remote_localizations_guard
+        .as_ref()
+        .is_some_and(|remote| remote.source == key)
+    {
+        *remote_localizations_guard = None;
+    }
+    drop(remote_localizations_guard);
+
+    app_state_guard.save_settings(&app_handle).map_err(|e| {
+        error!("Failed to save settings: {:?}", e);
+        e.to_string()
+    })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+/// Returns the key of the source with `url`, adding it first if it is not configured.
+async fn ensure_source(
+    app_handle: &tauri::AppHandle,
+    url: &str,
+    name: Option<&str>,
+) -> Result<String, String> {
+    let state = app_handle.state::<AppStateMutex>();
+
+    let key = {
+        let app_state_guard = state.lock().await;
+        let sources = &app_state_guard.settings.sources;
+
+        if let Some((key, _)) = sources.iter().find(|(_, source)| source.url == url) {
+            return Ok(key.clone());
+        }
+
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_default();
+        let base_key = deep_link::make_source_key(name.unwrap_or(&host));
+
+        std::iter::once(base_key.clone())
+            .chain((2..).map(|n| format!("{}-{}", base_key, n)))
+            .find(|key| !sources.contains_key(key))
+            .unwrap()
+    };
+
+    let name = name
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| key.clone());
+    add_source_to_settings(app_handle, &key, &name, url).await?;
+    Ok(key)
+}
+
+async fn apply_deep_link(
+    app_handle: &tauri::AppHandle,
+    link: deep_link::DeepLink,
+) -> Result<(), String> {
+    match link {
+        deep_link::DeepLink::AddSource { key, name, url } => {
+            add_source_to_settings(app_handle, &key, &name, &url).await?;
+        }
+        deep_link::DeepLink::Install { id, source_url } => {
+            let source = ensure_source(app_handle, &source_url, None).await?;
+
+            let localizations = utils::fetch_available_localizations(&source_url)
+                .await
+                .map_err(|e| {
+                    error!("Failed to fetch available localizations: {:?}", e);
+                    e.to_string()
+                })?;
+
+            let localization = localizations
+                .into_iter()
+                .find(|localization| localization.id == id)
+                .ok_or_else(|| format!("Localization '{}' not found in source", id))?;
+
+            let (profile, _) = get_active_profile(&app_handle.state::<AppStateMutex>()).await?;
+
+            enqueue_operation(
+                app_handle,
+                queue::Operation::Install {
+                    profile,
+                    source,
+                    localization,
+                    select: true,
+                    force: false,
+                },
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses an `llm://` link and applies it once the user confirms.
+fn handle_deep_link(app_handle: &tauri::AppHandle, link: &str) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    info!("Received deep link: {}", link);
+
+    let deep_link = match deep_link::parse(link) {
+        Ok(deep_link) => deep_link,
+        Err(e) => {
+            error!("Invalid deep link: {:?}", e);
+            app_handle
+                .dialog()
+                .message(format!("{:#}", e))
+                .title("Invalid link")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+            return;
+        }
+    };
+
+    let (message, confirm_label) = match &deep_link {
+        deep_link::DeepLink::AddSource { name, url, .. } => (
+            format!(
+                "Add localization source \"{}\"?\n\n{}\n\nOnly add sources you trust.",
+                name, url
+            ),
+            "Add source",
+        ),
+        deep_link::DeepLink::Install { id, source_url } => (
+            format!(
+                "Install localization \"{}\" from\n\n{}\n\nThe source will be added if needed. Only install localizations you trust.",
+                id, source_url
+            ),
+            "Install",
+        ),
+    };
+
+    let app_handle = app_handle.clone();
+    app_handle
+        .dialog()
+        .message(message)
+        .title("Limbus Localization Manager")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            confirm_label.to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if !confirmed {
+                info!("Deep link was declined");
+                return;
+            }
+
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = apply_deep_link(&app_handle, deep_link).await {
+                    error!("Failed to apply deep link: {}", e);
+                    app_handle.emit("deep_link:failed", e).unwrap();
+                }
+            });
+        });
+}
+
+fn show_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Brings the window forward on another launch without arguments.
+/// It is only shown if a headless Update & Play run hid it.
+fn focus_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if !window.is_visible().unwrap_or(true) {
+            let _ = window.show();
+        }
+        let _ = window.set_focus();
+    }
+}
+
+/// Selects a source by its key or display name.
+async fn select_source(app_handle: &tauri::AppHandle, name: &str) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
+    let mut app_state_guard = state.lock().await;
+
+    let key = app_state_guard
+        .settings
+        .sources
+        .iter()
+        .find(|(key, source)| key.as_str() == name || source.name.eq_ignore_ascii_case(name))
+        .map(|(key, _)| key.clone())
+        .ok_or_else(|| format!("Source '{}' not found", name))?;
+
+    if app_state_guard.settings.selected_source.as_ref() == Some(&key) {
+        return Ok(());
+    }
+
+    let mut new_settings = app_state_guard.settings.clone();
+    new_settings.selected_source = Some(key);
+
+    *app_handle.state::<RemoteLocalizationsMutex>().lock().await = None;
+
+    app_state_guard
+        .update_settings(app_handle, &new_settings)
+        .map_err(|e| {
+            error!("Failed to update settings: {:?}", e);
+            e.to_string()
+        })?;
+
+    app_handle
+        .emit("app_state_updated", app_state_guard.clone())
+        .map_err(|e| {
+            error!("Failed to emit app state updated: {:?}", e);
+            e.to_string()
+        })?;
+
+    Ok(())
+}
+
+/// Installs localizations from the selected source into the active profile,
+/// queueing them instead while the game is running.
+async fn install_from_selected_source(
+    app_handle: &tauri::AppHandle,
+    ids: &[String],
+) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
+
+    let (profile, source) = get_active_profile(&state).await?;
+    let source = source.ok_or_else(|| "No active source selected".to_string())?;
+    let source_url = state
+        .lock()
+        .await
+        .settings
+        .sources
+        .get(&source)
+        .ok_or_else(|| "No active source selected".to_string())?
+        .url
+        .clone();
+
+    let localizations = utils::fetch_available_localizations(&source_url)
+        .await
         .map_err(|e| {
-            error!("Failed to update settings: {:?}", e);
+            error!("Failed to fetch available localizations: {:?}", e);
             e.to_string()
         })?;
 
+    for id in ids {
+        let localization = localizations
+            .iter()
+            .find(|localization| &localization.id == id)
+            .cloned()
+            .ok_or_else(|| format!("Localization '{}' not found in source", id))?;
+
+        if app_handle.state::<GameWatcher>().is_running() {
+            enqueue_operation(
+                app_handle,
+                queue::Operation::Install {
+                    profile: profile.clone(),
+                    source: source.clone(),
+                    localization,
+                    select: true,
+                    force: false,
+                },
+            )
+            .await?;
+            continue;
+        }
+
+        info!("Installing localization {}", id);
+        install_to_profile(
+            app_handle,
+            &profile,
+            &localization,
+            &source,
+            true,
+            false,
+            audit_log::AuditAction::Install,
+        )
+        .await?;
+    }
+
+    let app_state = state.lock().await.clone();
     app_handle
-        .emit("app_state_updated", app_state_guard.clone())
+        .emit("app_state_updated", app_state)
         .map_err(|e| {
             error!("Failed to emit app state updated: {:?}", e);
             e.to_string()
@@ -191,145 +2117,137 @@ async fn update_settings(
     Ok(())
 }
 
-#[tauri::command]
-async fn install_localization(
-    app_handle: tauri::AppHandle,
-    state: State<'_, AppStateMutex>,
-    localization_lock: State<'_, LocalizationLocks>,
-    localization: utils::Localization,
+async fn apply_cli_args(
+    app_handle: &tauri::AppHandle,
+    cli_args: cli::CliArgs,
 ) -> Result<(), String> {
-    if steam::is_game_running() {
-        return Err("Game is running".to_string());
+    if let Some(source) = &cli_args.source {
+        select_source(app_handle, source).await?;
     }
 
-    let game_path;
-    let source;
-
-    {
-        let app_state_guard = state.lock().await;
-
-        source = app_state_guard
-            .settings
-            .selected_source
-            .clone()
-            .ok_or_else(|| "No active source selected".to_string())?;
+    if !cli_args.install.is_empty() {
+        install_from_selected_source(app_handle, &cli_args.install).await?;
+    }
 
-        let game_directory = app_state_guard.settings.game_directory.clone();
-        game_path = if let Some(game_directory) = game_directory {
-            std::path::PathBuf::from(game_directory)
-        } else {
-            steam::get_game_directory().map_err(|e| {
-                error!("Failed to get game directory: {:?}", e);
-                e.to_string()
-            })?
-        };
+    if cli_args.update_and_play {
+        run_update_and_play(app_handle.clone()).await?;
     }
 
-    let lock = localization_lock
-        .entry((localization.id.clone(), game_path.clone()))
-        .or_insert_with(|| Mutex::new(()));
-    let _aquired_lock = lock.lock().await;
+    Ok(())
+}
 
-    utils::install_localization(&game_path, &localization)
-        .await
-        .map_err(|e| {
-            error!("Failed to install localization: {:?}", e);
-            e.to_string()
-        })?;
+/// Runs the actions passed on the command line, or just brings the window up.
+/// With `exit_when_done` the app quits after a successful run instead of showing its window.
+fn handle_cli_args(app_handle: &tauri::AppHandle, args: &[String], exit_when_done: bool) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+    let cli_args = match cli::parse(args) {
+        Ok(cli_args) => cli_args,
+        Err(e) => {
+            error!("Invalid command line arguments: {:?}", e);
+            show_main_window(app_handle);
+            app_handle
+                .dialog()
+                .message(format!("{:#}", e))
+                .title("Invalid command line arguments")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+            return;
+        }
+    };
 
-    utils::install_fonts_for_localization(&game_path, &localization)
-        .await
-        .map_err(|e| {
-            error!("Failed to install fonts for localization: {:?}", e);
-            e.to_string()
-        })?;
+    if cli_args.is_empty() {
+        focus_main_window(app_handle);
+        return;
+    }
 
-    {
-        let mut app_state_guard = state.lock().await;
+    info!("Running command line actions: {:?}", cli_args);
 
-        match app_state_guard.installed_metadata {
-            Some(ref mut installed_metadata) => {
-                installed_metadata.installed.insert(
-                    localization.id.clone(),
-                    utils::InstalledLocalization {
-                        id: localization.id.clone(),
-                        version: localization.version.clone(),
-                        source,
-                    },
-                );
-            }
-            None => {
-                app_state_guard.installed_metadata = Some(
-                    utils::InstalledMetadata::with_localization(&localization, &source),
-                );
-            }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = apply_cli_args(&app_handle, cli_args).await;
+        if let Err(e) = &result {
+            error!("Command line actions failed: {}", e);
+            app_handle.emit("cli:failed", e).unwrap();
         }
 
-        app_state_guard.save_installed_metadata().map_err(|e| {
-            error!("Failed to save installed metadata: {:?}", e);
-            e.to_string()
-        })?;
+        // Post-exit hooks and queued operations need the app to keep watching the game
+        let has_post_exit_hooks = !app_handle
+            .state::<AppStateMutex>()
+            .lock()
+            .await
+            .settings
+            .hooks
+            .post_exit
+            .is_empty();
+        let has_queued_operations = app_handle
+            .state::<OperationQueueMutex>()
+            .lock()
+            .await
+            .has_pending();
 
-        app_handle
-            .emit("app_state_updated", app_state_guard.clone())
-            .map_err(|e| {
-                error!("Failed to emit app state updated: {:?}", e);
-                e.to_string()
-            })?;
-    }
+        if result.is_ok() && exit_when_done && !has_post_exit_hooks && !has_queued_operations {
+            app_handle.exit(0);
+            return;
+        }
 
-    Ok(())
+        show_main_window(&app_handle);
+    });
 }
 
 #[tauri::command]
-async fn uninstall_localization(
-    app_handle: tauri::AppHandle,
+async fn export_settings_bundle(
     state: State<'_, AppStateMutex>,
-    localization_lock: State<'_, LocalizationLocks>,
-    localization: utils::Localization,
+    path: String,
 ) -> Result<(), String> {
-    if steam::is_game_running() {
-        return Err("Game is running".to_string());
-    }
+    let app_state_guard = state.lock().await;
 
-    let game_path;
+    let mut settings = app_state_guard.settings.clone();
+    settings.sync_active_profile();
 
-    {
-        let app_state_guard = state.lock().await;
+    let settings_bundle =
+        bundle::SettingsBundle::new(&settings, app_state_guard.installed_metadata.as_ref());
+    drop(app_state_guard);
 
-        game_path = if let Some(game_directory) = &app_state_guard.settings.game_directory {
-            std::path::PathBuf::from(game_directory)
-        } else {
-            steam::get_game_directory().map_err(|e| {
-                error!("Failed to get game directory: {:?}", e);
-                e.to_string()
-            })?
-        };
-    }
+    bundle::export_bundle(std::path::Path::new(&path), &settings_bundle).map_err(|e| {
+        error!("Failed to export settings bundle: {:?}", e);
+        e.to_string()
+    })
+}
 
-    let lock = localization_lock
-        .entry((localization.id.clone(), game_path.clone()))
-        .or_insert_with(|| Mutex::new(()));
-    let _aquired_lock = lock.lock().await;
+/// Imports sources and settings from a bundle and queues installation of the
+/// bundled localizations into the active profile.
+#[tauri::command]
+async fn import_settings_bundle(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppStateMutex>,
+    path: String,
+    conflict_policy: Option<bundle::ConflictPolicy>,
+) -> Result<bundle::ImportReport, String> {
+    let settings_bundle = bundle::load_bundle(std::path::Path::new(&path)).map_err(|e| {
+        error!("Failed to load settings bundle: {:?}", e);
+        e.to_string()
+    })?;
 
-    utils::uninstall_localization(&game_path, &localization)
-        .await
-        .map_err(|e| {
-            error!("Failed to uninstall localization: {:?}", e);
-            e.to_string()
-        })?;
+    let mut report = bundle::ImportReport::default();
+    let profile_name;
+    let sources;
+    let installed_metadata;
+    let source_keys;
 
     {
         let mut app_state_guard = state.lock().await;
 
-        app_state_guard
-            .installed_metadata
-            .as_mut()
-            .unwrap()
-            .installed
-            .remove(&localization.id);
-        app_state_guard.save_installed_metadata().map_err(|e| {
-            error!("Failed to save installed metadata: {:?}", e);
+        app_state_guard.settings.sync_active_profile();
+        source_keys = settings_bundle.apply_settings(
+            &mut app_state_guard.settings,
+            conflict_policy.unwrap_or(bundle::ConflictPolicy::Keep),
+            &mut report,
+        );
+        app_state_guard.settings.sync_active_profile();
+
+        app_state_guard.save_settings(&app_handle).map_err(|e| {
+            error!("Failed to save settings: {:?}", e);
             e.to_string()
         })?;
 
@@ -339,72 +2257,158 @@ async fn uninstall_localization(
                 error!("Failed to emit app state updated: {:?}", e);
                 e.to_string()
             })?;
+
+        profile_name = app_state_guard
+            .settings
+            .active_profile
+            .clone()
+            .ok_or_else(|| "No active profile selected".to_string())?;
+        sources = app_state_guard.settings.sources.clone();
+        installed_metadata = app_state_guard
+            .installed_metadata
+            .clone()
+            .unwrap_or_else(utils::InstalledMetadata::new);
     }
 
-    Ok(())
+    let mut manifests = std::collections::HashMap::new();
+
+    for source_key in settings_bundle.get_installed_source_keys(&source_keys) {
+        let Some(source) = sources.get(&source_key) else {
+            warn!("Source '{}' is not configured", source_key);
+            continue;
+        };
+
+        match utils::fetch_available_localizations(&source.url).await {
+            Ok(localizations) => {
+                manifests.insert(source_key, localizations);
+            }
+            Err(e) => error!("Failed to fetch source '{}': {:?}", source_key, e),
+        }
+    }
+
+    let installs =
+        settings_bundle.plan_installs(&source_keys, &manifests, &installed_metadata, &mut report);
+
+    for (source_key, localization) in installs {
+        enqueue_operation(
+            &app_handle,
+            queue::Operation::Install {
+                profile: profile_name.clone(),
+                source: source_key,
+                localization,
+                select: false,
+                force: false,
+            },
+        )
+        .await?;
+    }
+
+    Ok(report)
 }
 
 #[tauri::command]
-async fn repair_localization(
-    app_handle: tauri::AppHandle,
-    state: State<'_, AppStateMutex>,
-    localization_lock: State<'_, LocalizationLocks>,
-    localization: utils::Localization,
-) -> Result<(), String> {
-    install_localization(app_handle, state, localization_lock, localization).await?;
-    Ok(())
+async fn get_game_language(state: State<'_, AppStateMutex>) -> Result<Option<String>, String> {
+    let app_state_guard = state.lock().await;
+
+    let game_path = app_state_guard.get_game_path().map_err(|e| {
+        error!("Failed to get game directory: {:?}", e);
+        e.to_string()
+    })?;
+
+    language::get_selected_language(&game_path).map_err(|e| {
+        error!("Failed to get game language: {:?}", e);
+        e.to_string()
+    })
 }
 
-#[tauri::command]
-async fn set_game_directory(
-    app_handle: tauri::AppHandle,
-    state: State<'_, AppStateMutex>,
-    directory: Option<String>,
+/// Selects `language` in the game of a profile and remembers it for that profile.
+async fn set_profile_language(
+    app_handle: &tauri::AppHandle,
+    profile_name: &str,
+    language: Option<&str>,
 ) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
     let mut app_state_guard = state.lock().await;
 
+    let game_path = app_state_guard
+        .get_profile_game_path(profile_name)
+        .map_err(|e| {
+            error!("Failed to get game directory: {:?}", e);
+            e.to_string()
+        })?;
+
+    let mut result = Ok(());
     app_state_guard
-        .update_game_directory(&directory)
+        .update_profile_metadata(profile_name, |game_path, installed_metadata| {
+            result = language::select_language(game_path, installed_metadata, language);
+        })
         .map_err(|e| {
-            error!("Failed to update game directory: {:?}", e);
+            error!("Failed to save installed metadata: {:?}", e);
             e.to_string()
         })?;
 
-    app_state_guard.save_settings(&app_handle).map_err(|e| {
-        error!("Failed to save settings: {:?}", e);
+    result.map_err(|e| {
+        error!("Failed to set game language: {:?}", e);
         e.to_string()
     })?;
 
-    app_handle
-        .emit("app_state_updated", app_state_guard.clone())
-        .map_err(|e| {
-            error!("Failed to emit app state updated: {:?}", e);
+    if app_state_guard.remember_game_language(profile_name, &game_path) {
+        app_state_guard.save_settings(app_handle).map_err(|e| {
+            error!("Failed to save settings: {:?}", e);
             e.to_string()
         })?;
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-async fn update_and_play(
+async fn set_game_language(
     app_handle: tauri::AppHandle,
     state: State<'_, AppStateMutex>,
-    localization_lock: State<'_, LocalizationLocks>,
-    remote_localizations_state: State<'_, RemoteLocalizationsMutex>,
+    game_watcher: State<'_, GameWatcher>,
+    language: Option<String>,
 ) -> Result<(), String> {
-    app_handle.emit("play:started", ()).unwrap();
+    let (profile, _) = get_active_profile(&state).await?;
 
-    if steam::is_game_running() {
-        app_handle.emit("play:game_running", ()).unwrap();
-        return Err("Game is already running".to_string());
+    // Like other changes to the game directory, this waits until the game exits
+    if game_watcher.is_running() {
+        return enqueue_operation(
+            &app_handle,
+            queue::Operation::SetLanguage { profile, language },
+        )
+        .await;
     }
 
+    set_profile_language(&app_handle, &profile, language.as_deref()).await
+}
+
+#[tauri::command]
+async fn update_and_play(app_handle: tauri::AppHandle) -> Result<(), String> {
+    run_update_and_play(app_handle).await
+}
+
+/// Updates installed localizations from the selected source and launches the game.
+async fn run_update_and_play(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppStateMutex>();
+    let localization_lock = app_handle.state::<LocalizationLocks>();
+    let font_cache_lock = app_handle.state::<FontCacheLock>();
+    let game_watcher = app_handle.state::<GameWatcher>();
+    let remote_localizations_state = app_handle.state::<RemoteLocalizationsMutex>();
+
+    app_handle.emit("play:started", ()).unwrap();
+
+    let active_profile;
     let active_source;
     let source_url;
     let game_path;
+    let game_build;
+    let font_cache_dir;
+    let archive_cache;
 
     {
-        let app_state_guard = state.lock().await;
+        let mut app_state_guard = state.lock().await;
+        active_profile = app_state_guard.settings.active_profile.clone();
         active_source = app_state_guard
             .settings
             .selected_source
@@ -420,16 +2424,38 @@ async fn update_and_play(
             .url
             .clone();
 
-        game_path = if let Some(game_directory) = &app_state_guard.settings.game_directory {
-            std::path::PathBuf::from(game_directory)
-        } else {
-            steam::get_game_directory().map_err(|e| {
-                error!("Failed to get game directory: {:?}", e);
+        game_path = app_state_guard.get_game_path().map_err(|e| {
+            error!("Failed to get game directory: {:?}", e);
+            e.to_string()
+        })?;
+
+        font_cache_dir = app_state_guard
+            .get_font_cache_dir(&app_handle)
+            .map_err(|e| {
+                error!("Failed to get font cache directory: {:?}", e);
                 e.to_string()
-            })?
-        };
+            })?;
+
+        archive_cache = app_state_guard
+            .get_archive_cache(&app_handle)
+            .map_err(|e| {
+                error!("Failed to get archive cache: {:?}", e);
+                e.to_string()
+            })?;
+
+        app_state_guard.refresh_game_build();
+        game_build = app_state_guard.game_build.clone();
+    }
+
+    if let Some(game_build) = game_build.as_ref().filter(|b| b.update_in_progress) {
+        warn!("Game update is in progress");
+        app_handle
+            .emit("play:game_update_in_progress", game_build)
+            .unwrap();
     }
 
+    let game_build_id = game_build.and_then(|game_build| game_build.build_id);
+
     let remote_localizations = utils::fetch_available_localizations(&source_url)
         .await
         .map_err(|e| {
@@ -476,7 +2502,18 @@ async fn update_and_play(
             }
 
             let remote_localization = remote_localization.unwrap();
-            if remote_localization.version == localization.version {
+            if let Err(e) = remote_localization.check_compatibility(game_build_id) {
+                warn!("{}", e);
+                app_handle
+                    .emit("play:incompatible", &localization.id)
+                    .unwrap();
+                return None;
+            }
+
+            // Metadata restored after a game reinstall can outlive the files themselves
+            if remote_localization.version == localization.version
+                && utils::is_localization_present(&game_path, &localization.id)
+            {
                 info!("Localization {} is up to date", &localization.id);
                 app_handle
                     .emit("play:up_to_date", &localization.id)
@@ -484,35 +2521,72 @@ async fn update_and_play(
                 return None;
             }
 
-            Some((localization.id.clone(), remote_localization.clone()))
+            Some((
+                localization.id.clone(),
+                localization.version.clone(),
+                remote_localization.clone(),
+            ))
         })
         .collect();
 
-    for (localization_id, remote_localization) in localizations_to_update {
+    // The files can't be replaced while the game is running, so the updates
+    // wait in the operation queue until it exits
+    if game_watcher.is_running() {
+        let profile = active_profile.ok_or_else(|| "No active profile selected".to_string())?;
+
+        for (localization_id, _, remote_localization) in localizations_to_update {
+            info!("Game is running, queueing update of {}", &localization_id);
+            enqueue_operation(
+                &app_handle,
+                queue::Operation::Install {
+                    profile: profile.clone(),
+                    source: active_source.clone(),
+                    localization: remote_localization,
+                    select: false,
+                    force: false,
+                },
+            )
+            .await?;
+        }
+
+        app_handle.emit("play:game_running", ()).unwrap();
+        app_handle.emit("play:finished", ()).unwrap();
+        return Ok(());
+    }
+
+    for (localization_id, installed_version, remote_localization) in localizations_to_update {
         info!(
             "Updating localization {} to version {}",
             &localization_id, &remote_localization.version
         );
         app_handle.emit("play:updating", &localization_id).unwrap();
 
-        let lock = localization_lock
-            .entry((localization_id.clone(), game_path.clone()))
-            .or_insert_with(|| Mutex::new(()));
-        let _aquired_lock = lock.lock().await;
-
-        utils::install_localization(&game_path, &remote_localization)
-            .await
-            .map_err(|e| {
-                error!("Failed to install localization: {:?}", e);
-                e.to_string()
-            })?;
+        // Same version means the files went missing
+        let action = if installed_version == remote_localization.version {
+            audit_log::AuditAction::Repair
+        } else {
+            audit_log::AuditAction::Update
+        };
+        let mut event =
+            audit_log::AuditEvent::new(action, active_profile.as_deref(), &localization_id);
+        event.old_version = Some(installed_version);
+        event.new_version = Some(remote_localization.version.clone());
+        event.source = Some(active_source.clone());
+        event.game_build = game_build_id;
+
+        let started = std::time::Instant::now();
+        let result = install_localization_files(
+            &localization_lock,
+            &font_cache_lock,
+            &game_path,
+            &archive_cache,
+            &font_cache_dir,
+            &remote_localization,
+        )
+        .await;
 
-        utils::install_fonts_for_localization(&game_path, &remote_localization)
-            .await
-            .map_err(|e| {
-                error!("Failed to install fonts for localization: {:?}", e);
-                e.to_string()
-            })?;
+        record_audit_event(&app_handle, event, started, &result);
+        result?;
 
         app_handle
             .emit("play:update_finished", &localization_id)
@@ -524,32 +2598,44 @@ async fn update_and_play(
             .installed_metadata
             .as_mut()
             .unwrap()
-            .installed
-            .insert(
-                remote_localization.id.clone(),
-                utils::InstalledLocalization {
-                    id: remote_localization.id.clone(),
-                    version: remote_localization.version.clone(),
-                    source: active_source.clone(),
-                },
-            );
+            .add_localization(&remote_localization, &active_source, game_build_id);
     }
 
-    let state_guard = state.lock().await;
-    state_guard.save_installed_metadata().map_err(|e| {
-        error!("Failed to save installed metadata: {:?}", e);
-        e.to_string()
-    })?;
+    let launch_settings;
+    let pre_launch_hooks;
 
-    app_handle
-        .emit("app_state_updated", state_guard.clone())
-        .map_err(|e| {
-            error!("Failed to emit app state updated: {:?}", e);
+    {
+        let state_guard = state.lock().await;
+        state_guard.save_installed_metadata().map_err(|e| {
+            error!("Failed to save installed metadata: {:?}", e);
             e.to_string()
         })?;
 
+        app_handle
+            .emit("app_state_updated", state_guard.clone())
+            .map_err(|e| {
+                error!("Failed to emit app state updated: {:?}", e);
+                e.to_string()
+            })?;
+
+        launch_settings = state_guard.settings.launch.clone();
+        pre_launch_hooks = state_guard.settings.hooks.pre_launch.clone();
+    }
+
+    hooks::run_hooks(
+        &app_handle,
+        hooks::HookStage::PreLaunch,
+        &pre_launch_hooks,
+        &game_path,
+    )
+    .await
+    .map_err(|e| {
+        error!("Pre-launch hook failed: {:?}", e);
+        e.to_string()
+    })?;
+
     app_handle.emit("play:starting_game", ()).unwrap();
-    steam::launch_game().map_err(|e| {
+    launcher::launch_game(&launch_settings, &game_path).map_err(|e| {
         error!("Failed to launch game: {:?}", e);
         e.to_string()
     })?;
@@ -564,19 +2650,25 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_os::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            let _ = app
-                .get_webview_window("main")
-                .expect("no main window")
-                .set_focus();
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            handle_cli_args(app, &args, false);
+
+            if let Some(link) = deep_link::find_in_args(&args) {
+                handle_deep_link(app, link);
+            }
         }))
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
             let app_handle = app.handle();
 
+            // Shortcuts that only update and launch the game don't need the window
+            let args: Vec<String> = std::env::args().collect();
+            let headless = cli::parse(&args).is_ok_and(|cli_args| cli_args.update_and_play);
+
             use tauri::{LogicalSize, WebviewWindowBuilder, WebviewUrl};
             let window = WebviewWindowBuilder::new(app, "main", WebviewUrl::default())
                 .title("Limbus Localization Manager")
+                .visible(!headless)
                 .resizable(false)
                 .transparent(true)
                 .decorations(false)
@@ -597,6 +2689,33 @@ pub fn run() {
             let localization_locks_mutex: LocalizationLocks = DashMap::new();
             app.manage(localization_locks_mutex);
 
+            let font_cache_lock: FontCacheLock = Mutex::new(());
+            app.manage(font_cache_lock);
+
+            app.manage(GameWatcher::start(app_handle.clone()));
+
+            let operation_queue = queue::load_queue(app_handle).unwrap_or_else(|e| {
+                error!("Failed to load operation queue: {}", e);
+                queue::OperationQueue::default()
+            });
+            app.manage(Mutex::new(operation_queue));
+            app.manage(OperationQueueNotify::new());
+
+            tauri::async_runtime::spawn(watch_game_sessions(app_handle.clone()));
+
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
+            tauri::async_runtime::spawn_blocking(|| {
+                if let Err(e) = deep_link::register_scheme() {
+                    warn!("Failed to register link handler: {:?}", e);
+                }
+            });
+
+            handle_cli_args(app_handle, &args, headless);
+
+            if let Some(link) = deep_link::find_in_args(&args) {
+                handle_deep_link(app_handle, link);
+            }
+
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
@@ -604,11 +2723,32 @@ pub fn run() {
             get_available_localizations,
             get_app_state,
             get_latest_version,
+            is_game_running,
             update_settings,
             install_localization,
             uninstall_localization,
             repair_localization,
+            rollback_localization,
+            get_operation_queue,
+            remove_queued_operation,
+            retry_queued_operation,
             set_game_directory,
+            install_localization_to_profiles,
+            add_profile,
+            remove_profile,
+            switch_profile,
+            get_game_language,
+            set_game_language,
+            get_font_cache,
+            prune_font_cache,
+            get_install_history,
+            query_audit_log,
+            test_source,
+            add_source,
+            rename_source,
+            remove_source,
+            export_settings_bundle,
+            import_settings_bundle,
             update_and_play,
         ])
         .run(tauri::generate_context!())
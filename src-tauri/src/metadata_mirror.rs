@@ -0,0 +1,227 @@
+use crate::utils::{self, InstalledMetadata};
+use anyhow::Context;
+use log::{info, warn};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+/// Copy of a game directory's `llc_config.toml`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirroredMetadata {
+    pub game_path: String, // Normalized, see `normalize_path`
+    pub updated_at: u64,   // Unix timestamp
+    pub metadata: InstalledMetadata,
+}
+
+/// Keeps the installed metadata of every game directory in the app data dir,
+/// so it survives the game folder being wiped, e.g. by a Steam reinstall.
+#[derive(Clone, Debug)]
+pub struct MetadataMirror {
+    dir: PathBuf,
+}
+
+impl MetadataMirror {
+    pub fn new(app_handle: &tauri::AppHandle) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            dir: app_handle.path().app_data_dir()?.join("InstalledMetadata"),
+        })
+    }
+
+    fn get_mirror_path(&self, game_path: &Path) -> PathBuf {
+        let mut hasher = Md5::new();
+        hasher.update(normalize_path(game_path).to_string_lossy().as_bytes());
+        self.dir.join(format!("{:x}.toml", hasher.finalize()))
+    }
+
+    pub fn load(&self, game_path: &Path) -> Result<Option<MirroredMetadata>, anyhow::Error> {
+        let mirror_path = self.get_mirror_path(game_path);
+        if !mirror_path.exists() {
+            return Ok(None);
+        }
+
+        let mirror_content = fs::read_to_string(&mirror_path)
+            .with_context(|| format!("Failed to read metadata mirror {:?}", mirror_path))?;
+        let mirrored_metadata: MirroredMetadata = toml::from_str(&mirror_content)
+            .with_context(|| format!("Failed to parse metadata mirror {:?}", mirror_path))?;
+
+        Ok(Some(mirrored_metadata))
+    }
+
+    /// Stores `metadata` for `game_path`, replacing the previous copy.
+    pub fn save(
+        &self,
+        game_path: &Path,
+        metadata: &InstalledMetadata,
+    ) -> Result<(), anyhow::Error> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let mirrored_metadata = MirroredMetadata {
+            game_path: normalize_path(game_path).to_string_lossy().to_string(),
+            updated_at: timestamp,
+            metadata: metadata.clone(),
+        };
+
+        fs::create_dir_all(&self.dir).with_context(|| {
+            format!("Failed to create metadata mirror directory {:?}", self.dir)
+        })?;
+
+        let mirror_content = toml::to_string(&mirrored_metadata)?;
+        utils::write_file_atomic(&self.get_mirror_path(game_path), mirror_content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Loads the installed metadata of `game_path`, restoring it from the mirror
+    /// when the game directory has lost its copy.
+    pub fn load_installed_metadata(
+        &self,
+        game_path: &Path,
+    ) -> Result<InstalledMetadata, anyhow::Error> {
+        if game_path.is_dir() && !utils::has_installed_metadata(game_path) {
+            match self.load(game_path) {
+                Ok(Some(mirrored_metadata)) => {
+                    info!(
+                        "Restoring installed metadata of {:?} from mirror saved at {}",
+                        game_path, mirrored_metadata.updated_at
+                    );
+                    utils::save_installed_metadata(
+                        &game_path.to_path_buf(),
+                        &mirrored_metadata.metadata,
+                    )?;
+                    return Ok(mirrored_metadata.metadata);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to load metadata mirror: {:?}", e),
+            }
+        }
+
+        let installed_metadata = utils::load_installed_metadata(&game_path.to_path_buf())?;
+
+        // Start mirroring installations made before the mirror existed
+        match self.load(game_path) {
+            Ok(Some(_)) => {}
+            Ok(None) => self
+                .save(game_path, &installed_metadata)
+                .unwrap_or_else(|e| warn!("Failed to mirror installed metadata: {:?}", e)),
+            Err(e) => warn!("Failed to load metadata mirror: {:?}", e),
+        }
+
+        Ok(installed_metadata)
+    }
+}
+
+/// Makes `path` absolute and resolves `.` and `..` without touching the file
+/// system, so a game directory maps to the same mirror whether or not it exists.
+fn normalize_path(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    // Windows paths are case-insensitive
+    #[cfg(windows)]
+    let normalized = PathBuf::from(normalized.to_string_lossy().to_lowercase());
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::test_localization;
+
+    fn metadata(version: &str) -> InstalledMetadata {
+        let mut metadata = InstalledMetadata::new();
+        metadata.add_localization(&test_localization(version, &[]), "main", None);
+        metadata
+    }
+
+    #[test]
+    fn mirror_round_trip() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let game_dir = tempfile::tempdir().unwrap();
+        let mirror = MetadataMirror {
+            dir: data_dir.path().join("InstalledMetadata"),
+        };
+
+        assert!(mirror.load(game_dir.path()).unwrap().is_none());
+
+        mirror.save(game_dir.path(), &metadata("1.0")).unwrap();
+        mirror.save(game_dir.path(), &metadata("2.0")).unwrap();
+
+        let mirrored_metadata = mirror.load(game_dir.path()).unwrap().unwrap();
+        assert_eq!(mirrored_metadata.metadata.installed["ru"].version, "2.0");
+        assert_eq!(
+            mirrored_metadata.game_path,
+            normalize_path(game_dir.path()).to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn same_directory_uses_the_same_mirror() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let root_dir = tempfile::tempdir().unwrap();
+        let game_path = root_dir.path().join("Limbus Company");
+        fs::create_dir_all(&game_path).unwrap();
+        let mirror = MetadataMirror {
+            dir: data_dir.path().join("InstalledMetadata"),
+        };
+
+        mirror.save(&game_path, &metadata("1.0")).unwrap();
+
+        let other_spellings = [
+            root_dir.path().join("Limbus Company/"),
+            root_dir.path().join("./Limbus Company"),
+            root_dir.path().join("Limbus Company/../Limbus Company"),
+        ];
+        for path in &other_spellings {
+            assert!(mirror.load(path).unwrap().is_some(), "{:?}", path);
+        }
+
+        // A wiped game directory must still find its mirror
+        fs::remove_dir_all(&game_path).unwrap();
+        for path in other_spellings.iter().chain([&game_path]) {
+            assert!(mirror.load(path).unwrap().is_some(), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn restores_deleted_metadata_from_mirror() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = game_dir.path().to_path_buf();
+        let mirror = MetadataMirror {
+            dir: data_dir.path().join("InstalledMetadata"),
+        };
+
+        // Metadata written before the mirror existed starts being mirrored on load
+        utils::save_installed_metadata(&game_path, &metadata("1.0")).unwrap();
+        utils::save_installed_metadata(&game_path, &metadata("1.0")).unwrap();
+        mirror.load_installed_metadata(&game_path).unwrap();
+
+        for entry in fs::read_dir(&game_path).unwrap() {
+            fs::remove_file(entry.unwrap().path()).unwrap();
+        }
+        assert!(!utils::has_installed_metadata(&game_path));
+
+        let restored = mirror.load_installed_metadata(&game_path).unwrap();
+        assert_eq!(restored.installed["ru"].version, "1.0");
+
+        assert!(utils::has_installed_metadata(&game_path));
+        let reloaded = utils::load_installed_metadata(&game_path).unwrap();
+        assert_eq!(reloaded.installed["ru"].version, "1.0");
+    }
+}
@@ -0,0 +1,305 @@
+use crate::utils::{self, Localization};
+use anyhow::Context;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Operation {
+    Install {
+        profile: String,
+        source: String,
+        localization: Localization,
+        select: bool,
+        force: bool,
+    },
+    Repair {
+        profile: String,
+        source: String,
+        localization: Localization,
+    },
+    Uninstall {
+        profile: String,
+        localization: Localization,
+    },
+    Rollback {
+        profile: String,
+        localization_id: String, // The previous version is looked up when it runs
+        force: bool,
+    },
+    SetLanguage {
+        profile: String,
+        language: Option<String>, // None clears the selection
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Pending,
+    Running,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueuedOperation {
+    pub id: u64,
+    pub operation: Operation,
+    pub status: OperationStatus,
+    pub error: Option<String>,
+    pub queued_at: u64, // Unix timestamp
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OperationQueue {
+    next_id: u64,
+    pub operations: Vec<QueuedOperation>,
+}
+
+impl OperationQueue {
+    pub fn push(&mut self, operation: Operation) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let queued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        self.operations.push(QueuedOperation {
+            id,
+            operation,
+            status: OperationStatus::Pending,
+            error: None,
+            queued_at,
+        });
+
+        id
+    }
+
+    /// Marks the oldest pending operation as running and returns it.
+    pub fn start_next(&mut self) -> Option<QueuedOperation> {
+        let operation = self
+            .operations
+            .iter_mut()
+            .find(|operation| operation.status == OperationStatus::Pending)?;
+
+        operation.status = OperationStatus::Running;
+        Some(operation.clone())
+    }
+
+    pub fn complete(&mut self, id: u64) {
+        self.operations.retain(|operation| operation.id != id);
+    }
+
+    pub fn fail(&mut self, id: u64, error: String) {
+        if let Some(operation) = self.operations.iter_mut().find(|o| o.id == id) {
+            operation.status = OperationStatus::Failed;
+            operation.error = Some(error);
+        }
+    }
+
+    pub fn retry(&mut self, id: u64) -> bool {
+        match self.operations.iter_mut().find(|o| o.id == id) {
+            Some(operation) if operation.status == OperationStatus::Failed => {
+                operation.status = OperationStatus::Pending;
+                operation.error = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes an operation that is not currently running.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let length = self.operations.len();
+        self.operations
+            .retain(|operation| operation.id != id || operation.status == OperationStatus::Running);
+        self.operations.len() != length
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.operations
+            .iter()
+            .any(|operation| operation.status == OperationStatus::Pending)
+    }
+}
+
+fn get_queue_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, anyhow::Error> {
+    let config_dir = app_handle.path().app_config_dir()?;
+    Ok(config_dir.join("queue.toml"))
+}
+
+fn read_queue(queue_path: &Path) -> Result<OperationQueue, anyhow::Error> {
+    let queue_content = fs::read_to_string(queue_path)
+        .with_context(|| format!("Failed to read queue {:?}", queue_path))?;
+    let queue: OperationQueue = toml::from_str(&queue_content)
+        .with_context(|| format!("Failed to parse queue {:?}", queue_path))?;
+    Ok(queue)
+}
+
+/// Reads the queue, falling back to the copy kept by the last save.
+/// Operations interrupted by a restart are started over.
+fn load_queue_from(queue_path: &Path) -> Result<OperationQueue, anyhow::Error> {
+    if !queue_path.exists() {
+        return Ok(OperationQueue::default());
+    }
+
+    let mut queue = match read_queue(queue_path) {
+        Ok(queue) => queue,
+        Err(e) => {
+            let backup_path = utils::get_backup_path(queue_path);
+            if !backup_path.exists() {
+                return Err(e);
+            }
+
+            warn!("{:#}, restoring {:?}", e, backup_path);
+            read_queue(&backup_path)?
+        }
+    };
+
+    for operation in queue.operations.iter_mut() {
+        if operation.status == OperationStatus::Running {
+            operation.status = OperationStatus::Pending;
+        }
+    }
+
+    Ok(queue)
+}
+
+fn save_queue_to(queue_path: &Path, queue: &OperationQueue) -> Result<(), anyhow::Error> {
+    if let Some(parent_dir) = queue_path.parent() {
+        fs::create_dir_all(parent_dir)?;
+    }
+
+    let queue_content = toml::to_string(queue)?;
+    utils::write_file_atomic(queue_path, queue_content.as_bytes())
+}
+
+pub fn load_queue(app_handle: &tauri::AppHandle) -> Result<OperationQueue, anyhow::Error> {
+    load_queue_from(&get_queue_path(app_handle)?)
+}
+
+pub fn save_queue(
+    app_handle: &tauri::AppHandle,
+    queue: &OperationQueue,
+) -> Result<(), anyhow::Error> {
+    save_queue_to(&get_queue_path(app_handle)?, queue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::test_localization;
+
+    fn test_queue() -> OperationQueue {
+        let mut queue = OperationQueue::default();
+        queue.push(Operation::Install {
+            profile: "Default".to_string(),
+            source: "github".to_string(),
+            localization: test_localization("2", &["Pretendard.ttf"]),
+            select: true,
+            force: false,
+        });
+        queue.push(Operation::Repair {
+            profile: "Default".to_string(),
+            source: "github".to_string(),
+            localization: test_localization("1", &[]),
+        });
+        queue.push(Operation::Uninstall {
+            profile: "Steam Deck".to_string(),
+            localization: test_localization("1", &[]),
+        });
+        queue
+    }
+
+    fn statuses(queue: &OperationQueue) -> Vec<(u64, OperationStatus)> {
+        queue
+            .operations
+            .iter()
+            .map(|operation| (operation.id, operation.status.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn queue_survives_a_restart() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let queue_path = config_dir.path().join("queue.toml");
+
+        let mut queue = test_queue();
+        queue.fail(1, "HTTP error 404".to_string());
+        save_queue_to(&queue_path, &queue).unwrap();
+
+        let mut loaded = load_queue_from(&queue_path).unwrap();
+        assert_eq!(statuses(&loaded), statuses(&queue));
+        assert_eq!(
+            loaded.operations[1].error.as_deref(),
+            Some("HTTP error 404")
+        );
+        assert!(matches!(
+            &loaded.operations[0].operation,
+            Operation::Install { localization, select: true, force: false, .. }
+                if localization.version == "2" && localization.fonts[0].name == "Pretendard.ttf"
+        ));
+
+        // Ids keep counting after a restart
+        assert_eq!(
+            loaded.push(Operation::Uninstall {
+                profile: "Default".to_string(),
+                localization: test_localization("2", &[]),
+            }),
+            3
+        );
+    }
+
+    #[test]
+    fn interrupted_operations_are_pending_after_load() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let queue_path = config_dir.path().join("queue.toml");
+
+        let mut queue = test_queue();
+        assert_eq!(queue.start_next().unwrap().id, 0);
+        queue.fail(2, "Localization 'ru' not found".to_string());
+        save_queue_to(&queue_path, &queue).unwrap();
+
+        let mut loaded = load_queue_from(&queue_path).unwrap();
+        assert_eq!(
+            statuses(&loaded),
+            vec![
+                (0, OperationStatus::Pending),
+                (1, OperationStatus::Pending),
+                (2, OperationStatus::Failed),
+            ]
+        );
+        assert_eq!(loaded.start_next().unwrap().id, 0);
+    }
+
+    #[test]
+    fn missing_queue_is_empty() {
+        let config_dir = tempfile::tempdir().unwrap();
+
+        let queue = load_queue_from(&config_dir.path().join("queue.toml")).unwrap();
+        assert!(queue.operations.is_empty());
+    }
+
+    #[test]
+    fn torn_queue_falls_back_to_backup() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let queue_path = config_dir.path().join("queue.toml");
+
+        let mut queue = test_queue();
+        save_queue_to(&queue_path, &queue).unwrap();
+        queue.complete(0);
+        save_queue_to(&queue_path, &queue).unwrap();
+
+        let queue_content = fs::read_to_string(&queue_path).unwrap();
+        fs::write(&queue_path, &queue_content[..queue_content.len() / 2]).unwrap();
+
+        let loaded = load_queue_from(&queue_path).unwrap();
+        assert_eq!(loaded.operations.len(), 3);
+    }
+}
@@ -1,31 +1,251 @@
+use crate::archive_cache;
+use crate::utils;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::path::BaseDirectory;
 use tauri::Manager;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct LocalizationSource {
     pub name: String,
     pub url: String,
 }
 
+pub const DEFAULT_PROFILE_NAME: &str = "Default";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GameProfile {
+    pub game_directory: Option<String>, // None means auto-detect through Steam
+    pub selected_source: Option<String>,
+    pub game_language: Option<String>, // Localization selected in the game, restored on switch
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchMode {
+    #[default]
+    SteamUrl, // steam://run/<app id>
+    SteamApplaunch, // steam -applaunch <app id> <extra args>
+    Direct,         // LimbusCompany.exe, through `wrapper_command` if set
+    Custom,         // `command_template`
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct LaunchSettings {
+    pub mode: LaunchMode,
+    pub extra_args: Vec<String>, // Game arguments for `steam_applaunch` and `direct`
+    pub wrapper_command: Option<String>, // Proton/Wine command for `direct`, e.g. "wine"
+    pub command_template: Option<String>, // Supports {game_dir}, {game_exe} and {app_id}
+}
+
+fn default_hook_timeout() -> u64 {
+    60
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Hook {
+    pub name: String,
+    pub command: String, // Supports {game_dir}, {game_exe} and {app_id}
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub continue_on_error: bool, // Keep going if the hook fails or times out
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct HookSettings {
+    pub pre_launch: Vec<Hook>, // Run by Update & Play after updates, before launching
+    pub post_exit: Vec<Hook>,  // Run when the game process exits
+}
+
+/// Version of the config.toml layout written by this build.
+pub const CONFIG_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct AppSettings {
+    pub config_version: u32,
     pub sources: HashMap<String, LocalizationSource>,
-    pub selected_source: Option<String>,
-    pub game_directory: Option<String>,
+    pub selected_source: Option<String>, // Mirrors the active profile
+    pub game_directory: Option<String>,  // Mirrors the active profile
     pub language: Option<String>,
+    pub profiles: HashMap<String, GameProfile>,
+    pub active_profile: Option<String>,
+    pub launch: LaunchSettings,
+    pub hooks: HookSettings,
+    pub font_cache_directory: Option<String>, // Defaults to the app cache dir
+    pub archive_cache_max_size: u64,          // Bytes, 0 disables the archive cache
+    pub known_default_sources: HashMap<String, LocalizationSource>, // Bundled sources as last offered
 }
 
-impl AppSettings {
-    pub fn default() -> Self {
+impl Default for AppSettings {
+    fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             sources: HashMap::new(),
             selected_source: None,
             game_directory: None,
             language: None,
+            profiles: HashMap::new(),
+            active_profile: None,
+            launch: LaunchSettings::default(),
+            hooks: HookSettings::default(),
+            font_cache_directory: None,
+            archive_cache_max_size: archive_cache::DEFAULT_MAX_SIZE,
+            known_default_sources: HashMap::new(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// Brings the bundled default sources into the user's sources:
+    /// - new defaults are added once, so removing one keeps it removed;
+    /// - defaults dropped from the bundle are removed, or moved to the new key
+    ///   if the bundle offers the same url under another key;
+    /// - changed defaults replace the old ones.
+    ///
+    /// Sources the user added or edited are never touched. Returns whether the settings changed.
+    pub fn merge_default_sources(
+        &mut self,
+        defaults: &HashMap<String, LocalizationSource>,
+    ) -> bool {
+        let mut changed = false;
+
+        let mut dropped_keys: Vec<String> = self
+            .known_default_sources
+            .keys()
+            .filter(|key| !defaults.contains_key(*key))
+            .cloned()
+            .collect();
+        dropped_keys.sort();
+
+        for key in dropped_keys {
+            let offered = self.known_default_sources.remove(&key).unwrap();
+            changed = true;
+
+            if self.sources.get(&key) != Some(&offered) {
+                continue;
+            }
+            self.sources.remove(&key);
+
+            let renamed_to = defaults
+                .iter()
+                .find(|(new_key, source)| {
+                    source.url == offered.url
+                        && !self.known_default_sources.contains_key(*new_key)
+                        && !self.sources.contains_key(*new_key)
+                })
+                .map(|(new_key, _)| new_key.clone());
+
+            match renamed_to {
+                Some(new_key) => {
+                    println!("Moving default source '{}' to '{}'", key, new_key);
+                    let source = defaults[&new_key].clone();
+                    self.sources.insert(new_key.clone(), source.clone());
+                    self.known_default_sources.insert(new_key.clone(), source);
+                    self.replace_source_references(&key, Some(&new_key));
+                }
+                None => {
+                    println!("Removing default source '{}'", key);
+                    self.replace_source_references(&key, None);
+                }
+            }
+        }
+
+        let mut default_keys: Vec<&String> = defaults.keys().collect();
+        default_keys.sort();
+
+        for key in default_keys {
+            let source = &defaults[key];
+
+            match self.known_default_sources.get(key) {
+                Some(offered) if offered == source => continue,
+                Some(offered) => {
+                    if self.sources.get(key) == Some(offered) {
+                        println!("Updating default source '{}'", key);
+                        self.sources.insert(key.clone(), source.clone());
+                    }
+                }
+                None => {
+                    if !self.sources.contains_key(key)
+                        && !self
+                            .sources
+                            .values()
+                            .any(|existing| existing.url == source.url)
+                    {
+                        println!("Adding default source '{}'", key);
+                        self.sources.insert(key.clone(), source.clone());
+                    }
+                }
+            }
+
+            self.known_default_sources
+                .insert(key.clone(), source.clone());
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Points the top-level and per-profile selected source at `replacement` instead of `key`.
+    pub fn replace_source_references(&mut self, key: &str, replacement: Option<&str>) {
+        self.sync_active_profile();
+        for profile in self.profiles.values_mut() {
+            if profile.selected_source.as_deref() == Some(key) {
+                profile.selected_source = replacement.map(|replacement| replacement.to_string());
+            }
+        }
+        self.apply_active_profile();
+    }
+
+    /// Makes sure there is an active profile, creating the default one
+    /// from the top-level settings for configs written before profiles existed.
+    pub fn ensure_active_profile(&mut self) {
+        if let Some(active_profile) = &self.active_profile {
+            if self.profiles.contains_key(active_profile) {
+                return;
+            }
+        }
+
+        let profile_name = DEFAULT_PROFILE_NAME.to_string();
+        self.profiles
+            .entry(profile_name.clone())
+            .or_insert_with(|| GameProfile {
+                game_directory: self.game_directory.clone(),
+                selected_source: self.selected_source.clone(),
+                game_language: None,
+            });
+        self.active_profile = Some(profile_name);
+        self.apply_active_profile();
+    }
+
+    /// Copies the top-level game directory and source into the active profile.
+    pub fn sync_active_profile(&mut self) {
+        if let Some(profile) = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get_mut(name))
+        {
+            profile.game_directory = self.game_directory.clone();
+            profile.selected_source = self.selected_source.clone();
+        }
+    }
+
+    /// Copies the active profile into the top-level game directory and source.
+    pub fn apply_active_profile(&mut self) {
+        if let Some(profile) = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+        {
+            self.game_directory = profile.game_directory.clone();
+            self.selected_source = profile.selected_source.clone();
         }
     }
 }
@@ -35,13 +255,10 @@ fn get_config_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, anyhow::Err
     Ok(config_dir.join("config.toml"))
 }
 
-fn load_default_settings(app_handle: &tauri::AppHandle) -> Result<AppSettings, anyhow::Error> {
-    let config_path = get_config_path(app_handle)?;
-
-    if let Some(parent_dir) = config_path.parent() {
-        fs::create_dir_all(parent_dir)?;
-    }
-
+/// Reads the settings bundled with the app, if present.
+fn load_bundled_settings(
+    app_handle: &tauri::AppHandle,
+) -> Result<Option<AppSettings>, anyhow::Error> {
     let resource_path = app_handle
         .path()
         .resolve("resources/default_config.toml", BaseDirectory::Resource)?;
@@ -53,28 +270,209 @@ fn load_default_settings(app_handle: &tauri::AppHandle) -> Result<AppSettings, a
             "Warning: default_config.toml not found at {:?}. Using hardcoded default.",
             resource_path
         );
-        let default_settings = AppSettings::default();
-        save_settings(app_handle, &default_settings)?;
-        return Ok(default_settings);
+        return Ok(None);
     }
 
     let default_config_content = fs::read_to_string(&resource_path)?;
-    let default_settings: AppSettings = toml::from_str(&default_config_content)?;
+    let (default_settings, _) = parse_settings(&default_config_content)?;
+    Ok(Some(default_settings))
+}
+
+fn load_default_settings(app_handle: &tauri::AppHandle) -> Result<AppSettings, anyhow::Error> {
+    let config_path = get_config_path(app_handle)?;
+
+    if let Some(parent_dir) = config_path.parent() {
+        fs::create_dir_all(parent_dir)?;
+    }
+
+    let mut default_settings = load_bundled_settings(app_handle)?.unwrap_or_default();
+    default_settings.known_default_sources = default_settings.sources.clone();
 
     save_settings(app_handle, &default_settings)?;
 
     Ok(default_settings)
 }
 
+type Migration = fn(&mut toml::Table);
+
+/// Upgrades from older config layouts, `MIGRATIONS[n]` turns version `n` into `n + 1`.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_to_profiles];
+
+/// Version 0 kept a single game directory and source at the top level
+/// and an `installed` table that is now stored in the game directory.
+fn migrate_to_profiles(config: &mut toml::Table) {
+    config.remove("installed");
+
+    if config.contains_key("profiles") {
+        return;
+    }
+
+    let mut profile = toml::Table::new();
+    for key in ["game_directory", "selected_source"] {
+        if let Some(value) = config.get(key) {
+            profile.insert(key.to_string(), value.clone());
+        }
+    }
+
+    let mut profiles = toml::Table::new();
+    profiles.insert(
+        DEFAULT_PROFILE_NAME.to_string(),
+        toml::Value::Table(profile),
+    );
+    config.insert("profiles".to_string(), toml::Value::Table(profiles));
+    config.insert(
+        "active_profile".to_string(),
+        toml::Value::String(DEFAULT_PROFILE_NAME.to_string()),
+    );
+}
+
+/// Applies pending migrations and returns the version the config was written with.
+fn migrate_config(config: &mut toml::Table) -> u32 {
+    let config_version = config
+        .get("config_version")
+        .and_then(|version| version.as_integer())
+        .unwrap_or(0)
+        .clamp(0, u32::MAX as i64) as u32;
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(config_version as usize) {
+        println!("Migrating config from version {}", version);
+        migration(config);
+    }
+
+    config.insert(
+        "config_version".to_string(),
+        toml::Value::Integer(config_version.max(CONFIG_VERSION) as i64),
+    );
+    config_version
+}
+
+fn get_config_backup_path(config_path: &Path, suffix: &str) -> PathBuf {
+    let mut backup_name = config_path.as_os_str().to_owned();
+    backup_name.push(format!(".{}.bak", suffix));
+    PathBuf::from(backup_name)
+}
+
+/// Copies the config next to itself as `config.toml.<suffix>.bak`.
+fn backup_config(config_path: &Path, suffix: &str) -> Result<PathBuf, anyhow::Error> {
+    let backup_path = get_config_backup_path(config_path, suffix);
+
+    fs::copy(config_path, &backup_path).with_context(|| {
+        format!(
+            "Failed to back up config {:?} to {:?}",
+            config_path, backup_path
+        )
+    })?;
+
+    println!("Config backed up to: {:?}", backup_path);
+    Ok(backup_path)
+}
+
+/// Like `backup_config`, but keeps an existing backup with the same suffix.
+fn backup_config_once(config_path: &Path, suffix: &str) -> Result<Option<PathBuf>, anyhow::Error> {
+    if get_config_backup_path(config_path, suffix).exists() {
+        return Ok(None);
+    }
+
+    backup_config(config_path, suffix).map(Some)
+}
+
+fn parse_settings(config_content: &str) -> Result<(AppSettings, u32), anyhow::Error> {
+    let mut config: toml::Table = toml::from_str(config_content)?;
+    let config_version = migrate_config(&mut config);
+    let settings = AppSettings::deserialize(config)?;
+    Ok((settings, config_version))
+}
+
+fn read_settings(config_path: &Path) -> Result<(AppSettings, u32), anyhow::Error> {
+    let config_content = fs::read_to_string(config_path)
+        .with_context(|| format!("Failed to read config {:?}", config_path))?;
+    parse_settings(&config_content)
+}
+
+/// Loads the copy kept by the last save and puts it back in place of the broken config.
+fn restore_backup(config_path: &Path) -> Option<(AppSettings, u32)> {
+    let backup_path = utils::get_backup_path(config_path);
+    if !backup_path.exists() {
+        return None;
+    }
+
+    let backup_content = match fs::read_to_string(&backup_path) {
+        Ok(backup_content) => backup_content,
+        Err(e) => {
+            eprintln!("Failed to read config backup: {}", e);
+            return None;
+        }
+    };
+
+    let result = match parse_settings(&backup_content) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Failed to parse config backup: {:#}", e);
+            return None;
+        }
+    };
+
+    // Write exactly what was parsed, keeping the backup itself in place
+    if let Err(e) = utils::restore_file_atomic(config_path, backup_content.as_bytes()) {
+        eprintln!("Failed to restore config backup: {:#}", e);
+        return None;
+    }
+
+    println!("Config restored from: {:?}", backup_path);
+    Some(result)
+}
+
 pub fn load_settings(app_handle: &tauri::AppHandle) -> Result<AppSettings, anyhow::Error> {
     let config_path = get_config_path(app_handle)?;
 
     if config_path.exists() {
-        let config_content = fs::read_to_string(&config_path)?;
-        let settings: AppSettings = toml::from_str(&config_content).unwrap_or_else(|e| {
-            eprintln!("Failed to parse config file: {}", e);
-            load_default_settings(app_handle).unwrap()
-        });
+        let (mut settings, config_version) = match read_settings(&config_path) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Failed to parse config file: {:#}", e);
+
+                // Keep the user's file around instead of silently replacing it
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or(0);
+                backup_config(&config_path, &format!("broken-{}", timestamp))?;
+
+                match restore_backup(&config_path) {
+                    Some(result) => result,
+                    None => return load_default_settings(app_handle),
+                }
+            }
+        };
+
+        let mut needs_save = false;
+
+        match load_bundled_settings(app_handle) {
+            Ok(Some(bundled_settings)) => {
+                needs_save |= settings.merge_default_sources(&bundled_settings.sources);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to load default sources: {}", e),
+        }
+
+        if config_version < CONFIG_VERSION {
+            backup_config(&config_path, &format!("v{}", config_version))?;
+            needs_save = true;
+        } else if config_version > CONFIG_VERSION {
+            // Settings unknown to this build are dropped once the user changes something,
+            // so the file is left as is and copied the first time it is seen
+            eprintln!(
+                "Config version {} is newer than supported version {}",
+                config_version, CONFIG_VERSION
+            );
+            backup_config_once(&config_path, &format!("v{}", config_version))?;
+            needs_save = false;
+        }
+
+        if needs_save {
+            save_settings(app_handle, &settings)?;
+        }
+
         return Ok(settings);
     }
 
@@ -92,7 +490,309 @@ pub fn save_settings(
 ) -> Result<(), anyhow::Error> {
     let config_path = get_config_path(app_handle)?;
     let config_content = toml::to_string(settings)?;
-    fs::write(&config_path, config_content)?;
+    utils::write_file_atomic(&config_path, config_content.as_bytes())?;
     println!("Settings saved to: {:?}", config_path);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION_0_CONFIG: &str = r#"
+selected_source = "ru"
+game_directory = "/games/Limbus Company"
+language = "ru"
+
+[sources.ru]
+name = "Russian"
+url = "https://example.com/ru.json"
+
+[installed.ru]
+id = "ru"
+version = "1.0"
+"#;
+
+    #[test]
+    fn migrates_version_0_config_to_profiles() {
+        let (settings, config_version) = parse_settings(VERSION_0_CONFIG).unwrap();
+
+        assert_eq!(config_version, 0);
+        assert_eq!(settings.config_version, CONFIG_VERSION);
+        assert_eq!(
+            settings.active_profile.as_deref(),
+            Some(DEFAULT_PROFILE_NAME)
+        );
+        assert_eq!(settings.language.as_deref(), Some("ru"));
+        assert_eq!(settings.sources["ru"].url, "https://example.com/ru.json");
+
+        let profile = &settings.profiles[DEFAULT_PROFILE_NAME];
+        assert_eq!(
+            profile.game_directory.as_deref(),
+            Some("/games/Limbus Company")
+        );
+        assert_eq!(profile.selected_source.as_deref(), Some("ru"));
+        assert_eq!(profile.game_language, None);
+    }
+
+    #[test]
+    fn migration_drops_installed_and_keeps_profiles() {
+        let mut config: toml::Table = toml::from_str(
+            r#"
+            active_profile = "Steam Deck"
+
+            [installed.ru]
+            id = "ru"
+
+            [profiles."Steam Deck"]
+            game_directory = "/deck/Limbus Company"
+            "#,
+        )
+        .unwrap();
+
+        migrate_to_profiles(&mut config);
+
+        assert!(!config.contains_key("installed"));
+        assert_eq!(config["active_profile"].as_str(), Some("Steam Deck"));
+        let profiles = config["profiles"].as_table().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles.contains_key("Steam Deck"));
+    }
+
+    #[test]
+    fn migration_without_game_directory_creates_empty_profile() {
+        let (settings, _) = parse_settings("").unwrap();
+
+        let profile = &settings.profiles[DEFAULT_PROFILE_NAME];
+        assert_eq!(profile.game_directory, None);
+        assert_eq!(profile.selected_source, None);
+    }
+
+    #[test]
+    fn keeps_version_of_newer_config() {
+        let (settings, config_version) = parse_settings(
+            r#"
+            config_version = 7
+            active_profile = "Default"
+            future_setting = true
+
+            [profiles.Default]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config_version, 7);
+        assert_eq!(settings.active_profile.as_deref(), Some("Default"));
+    }
+
+    #[test]
+    fn rejects_malformed_config() {
+        assert!(parse_settings("sources = [").is_err());
+        assert!(parse_settings("profiles = 5").is_err());
+    }
+
+    fn source(name: &str, url: &str) -> LocalizationSource {
+        LocalizationSource {
+            name: name.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    fn sources(entries: &[(&str, &LocalizationSource)]) -> HashMap<String, LocalizationSource> {
+        entries
+            .iter()
+            .map(|(key, source)| (key.to_string(), (*source).clone()))
+            .collect()
+    }
+
+    /// Settings as written on first start with `defaults`.
+    fn settings_with_defaults(defaults: &HashMap<String, LocalizationSource>) -> AppSettings {
+        let mut settings = AppSettings {
+            sources: defaults.clone(),
+            known_default_sources: defaults.clone(),
+            selected_source: defaults.keys().next().cloned(),
+            ..Default::default()
+        };
+        settings.ensure_active_profile();
+        settings
+    }
+
+    #[test]
+    fn adds_new_default_source() {
+        let github = source("Github", "https://example.com/github.json");
+        let mirror = source("Mirror", "https://example.com/mirror.json");
+        let mut settings = settings_with_defaults(&sources(&[("github", &github)]));
+
+        assert!(
+            settings.merge_default_sources(&sources(&[("github", &github), ("mirror", &mirror)]))
+        );
+        assert_eq!(
+            settings.sources,
+            sources(&[("github", &github), ("mirror", &mirror)])
+        );
+
+        assert!(
+            !settings.merge_default_sources(&sources(&[("github", &github), ("mirror", &mirror)]))
+        );
+    }
+
+    #[test]
+    fn keeps_default_source_removed_by_user() {
+        let github = source("Github", "https://example.com/github.json");
+        let mut settings = settings_with_defaults(&sources(&[("github", &github)]));
+        settings.sources.clear();
+
+        assert!(!settings.merge_default_sources(&sources(&[("github", &github)])));
+        assert!(settings.sources.is_empty());
+    }
+
+    #[test]
+    fn drops_unedited_default_source_removed_from_bundle() {
+        let github = source("Github", "https://example.com/github.json");
+        let mirror = source("Mirror", "https://example.com/mirror.json");
+        let mut settings =
+            settings_with_defaults(&sources(&[("github", &github), ("mirror", &mirror)]));
+        settings.selected_source = Some("mirror".to_string());
+        settings.sync_active_profile();
+
+        assert!(settings.merge_default_sources(&sources(&[("github", &github)])));
+
+        assert_eq!(settings.sources, sources(&[("github", &github)]));
+        assert_eq!(
+            settings.known_default_sources,
+            sources(&[("github", &github)])
+        );
+        assert_eq!(settings.selected_source, None);
+        assert_eq!(
+            settings.profiles[DEFAULT_PROFILE_NAME].selected_source,
+            None
+        );
+    }
+
+    #[test]
+    fn migrates_renamed_default_source() {
+        let github = source("Github", "https://example.com/github.json");
+        let official = source("Official", "https://example.com/github.json");
+        let mut settings = settings_with_defaults(&sources(&[("github", &github)]));
+        settings.profiles.insert(
+            "Steam Deck".to_string(),
+            GameProfile {
+                selected_source: Some("github".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(settings.merge_default_sources(&sources(&[("official", &official)])));
+
+        assert_eq!(settings.sources, sources(&[("official", &official)]));
+        assert_eq!(
+            settings.known_default_sources,
+            sources(&[("official", &official)])
+        );
+        assert_eq!(settings.selected_source.as_deref(), Some("official"));
+        for profile in settings.profiles.values() {
+            assert_eq!(profile.selected_source.as_deref(), Some("official"));
+        }
+    }
+
+    #[test]
+    fn updates_unedited_default_source() {
+        let github = source("Github", "https://example.com/github.json");
+        let moved = source("Github", "https://example.com/v2/github.json");
+        let mut settings = settings_with_defaults(&sources(&[("github", &github)]));
+
+        assert!(settings.merge_default_sources(&sources(&[("github", &moved)])));
+        assert_eq!(settings.sources, sources(&[("github", &moved)]));
+    }
+
+    #[test]
+    fn keeps_user_edited_and_added_sources() {
+        let github = source("Github", "https://example.com/github.json");
+        let mirror = source("Mirror", "https://example.com/mirror.json");
+        let edited = source("My Github", "https://example.com/github.json");
+        let custom = source("Custom", "https://example.com/custom.json");
+        let mut settings =
+            settings_with_defaults(&sources(&[("github", &github), ("mirror", &mirror)]));
+        settings
+            .sources
+            .insert("github".to_string(), edited.clone());
+        settings
+            .sources
+            .insert("custom".to_string(), custom.clone());
+
+        let official = source("Official", "https://example.com/github.json");
+        let moved_mirror = source("Mirror", "https://example.com/v2/mirror.json");
+        let mut edited_mirror = mirror.clone();
+        edited_mirror.name = "My mirror".to_string();
+        settings
+            .sources
+            .insert("mirror".to_string(), edited_mirror.clone());
+
+        assert!(settings.merge_default_sources(&sources(&[
+            ("official", &official),
+            ("mirror", &moved_mirror)
+        ])));
+
+        // The edited copy of the renamed default keeps its key and url, so the new key isn't added
+        assert_eq!(
+            settings.sources,
+            sources(&[
+                ("github", &edited),
+                ("mirror", &edited_mirror),
+                ("custom", &custom)
+            ])
+        );
+        assert_eq!(
+            settings.known_default_sources,
+            sources(&[("official", &official), ("mirror", &moved_mirror)])
+        );
+    }
+
+    #[test]
+    fn backs_up_newer_config_once() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("config.toml");
+
+        fs::write(&config_path, "config_version = 7").unwrap();
+        let backup_path = backup_config_once(&config_path, "v7").unwrap().unwrap();
+
+        fs::write(&config_path, "config_version = 7\nchanged = true").unwrap();
+        assert!(backup_config_once(&config_path, "v7").unwrap().is_none());
+        assert_eq!(
+            fs::read_to_string(&backup_path).unwrap(),
+            "config_version = 7"
+        );
+    }
+
+    #[test]
+    fn restores_broken_config_from_backup() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("config.toml");
+        let backup_path = utils::get_backup_path(&config_path);
+
+        fs::write(&config_path, "selected_source = [").unwrap();
+        fs::write(&backup_path, VERSION_0_CONFIG).unwrap();
+
+        let (settings, config_version) = restore_backup(&config_path).unwrap();
+        assert_eq!(config_version, 0);
+        assert_eq!(settings.language.as_deref(), Some("ru"));
+
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), VERSION_0_CONFIG);
+        assert_eq!(fs::read_to_string(&backup_path).unwrap(), VERSION_0_CONFIG);
+    }
+
+    #[test]
+    fn broken_backup_is_not_restored() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("config.toml");
+
+        fs::write(&config_path, "selected_source = [").unwrap();
+        fs::write(utils::get_backup_path(&config_path), "language = [").unwrap();
+
+        assert!(restore_backup(&config_path).is_none());
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "selected_source = ["
+        );
+    }
+}
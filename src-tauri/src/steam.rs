@@ -1,10 +1,75 @@
+use crate::vdf;
 use anyhow::{Context, Error};
+use log::warn;
+use serde::{Deserialize, Serialize};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use sysinfo::System;
+use sysinfo::{Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
 
-const LIMBUS_STEAM_ID: u32 = 1973530;
+pub const LIMBUS_STEAM_ID: u32 = 1973530;
+
+// Steam `StateFlags` bits from the app manifest
+const STATE_UPDATE_REQUIRED: u32 = 1 << 1;
+const STATE_UPDATE_RUNNING: u32 = 1 << 8;
+const STATE_UPDATE_PAUSED: u32 = 1 << 9;
+const STATE_UPDATE_STARTED: u32 = 1 << 10;
+const STATE_PREALLOCATING: u32 = 1 << 19;
+const STATE_DOWNLOADING: u32 = 1 << 20;
+const STATE_STAGING: u32 = 1 << 21;
+const STATE_COMMITTING: u32 = 1 << 22;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameBuildInfo {
+    pub build_id: Option<u64>, // None if the manifest has no usable `buildid`
+    pub state_flags: u32,
+    pub last_updated: u64,        // Unix timestamp of the last game update
+    pub update_pending: bool,     // Steam has an update queued or paused
+    pub update_in_progress: bool, // Steam is downloading or applying an update
+}
+
+impl GameBuildInfo {
+    fn from_manifest(manifest: &vdf::VdfValue) -> Result<Self, Error> {
+        let app_state = manifest
+            .get("AppState")
+            .ok_or_else(|| anyhow::anyhow!("Invalid game manifest"))?;
+
+        let parse_number = |key: &str| -> Result<u64, Error> {
+            app_state
+                .get_str(key)
+                .unwrap_or("0")
+                .parse::<u64>()
+                .with_context(|| format!("Invalid '{}' in game manifest", key))
+        };
+
+        // A made up build would make every localization with `min_build` incompatible
+        let build_id = app_state
+            .get_str("buildid")
+            .and_then(|build_id| build_id.parse::<u64>().ok());
+        if build_id.is_none() {
+            warn!("Game manifest has no valid build id");
+        }
+
+        let state_flags = u32::try_from(parse_number("StateFlags")?)
+            .context("Invalid 'StateFlags' in game manifest")?;
+        let last_updated = parse_number("LastUpdated")?;
+
+        Ok(Self {
+            build_id,
+            state_flags,
+            last_updated,
+            update_pending: state_flags & (STATE_UPDATE_REQUIRED | STATE_UPDATE_PAUSED) != 0,
+            update_in_progress: state_flags
+                & (STATE_UPDATE_RUNNING
+                    | STATE_UPDATE_STARTED
+                    | STATE_PREALLOCATING
+                    | STATE_DOWNLOADING
+                    | STATE_STAGING
+                    | STATE_COMMITTING)
+                != 0,
+        })
+    }
+}
 
 #[cfg(target_os = "windows")]
 pub fn launch_game() -> Result<(), Error> {
@@ -18,17 +83,79 @@ pub fn launch_game() -> Result<(), Error> {
     Ok(())
 }
 
+#[cfg(target_os = "linux")]
+const STEAM_FLATPAK_ID: &str = "com.valvesoftware.Steam";
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SteamInstallKind {
+    Native,
+    Flatpak,
+    Snap,
+}
+
+#[cfg(target_os = "linux")]
+fn find_steam_installation(home: &Path) -> Option<(PathBuf, SteamInstallKind)> {
+    let flatpak_home = format!(".var/app/{}", STEAM_FLATPAK_ID);
+    let candidates = [
+        (home.join(".steam/steam"), SteamInstallKind::Native),
+        (home.join(".local/share/Steam"), SteamInstallKind::Native),
+        (
+            home.join(&flatpak_home).join(".local/share/Steam"),
+            SteamInstallKind::Flatpak,
+        ),
+        (
+            home.join(&flatpak_home).join(".steam/steam"),
+            SteamInstallKind::Flatpak,
+        ),
+        (
+            home.join("snap/steam/common/.local/share/Steam"),
+            SteamInstallKind::Snap,
+        ),
+        (
+            home.join("snap/steam/common/.steam/steam"),
+            SteamInstallKind::Snap,
+        ),
+    ];
+
+    candidates.into_iter().find(|(path, _)| path.exists())
+}
+
 #[cfg(target_os = "linux")]
 pub fn launch_game() -> Result<(), Error> {
-    let result = Command::new("xdg-open")
-        .arg(format!("steam://run/{}", LIMBUS_STEAM_ID))
-        .spawn();
+    let steam_url = format!("steam://run/{}", LIMBUS_STEAM_ID);
+    let install_kind = dirs::home_dir()
+        .and_then(|home| find_steam_installation(&home))
+        .map(|(_, kind)| kind);
+
+    match install_kind {
+        Some(SteamInstallKind::Flatpak) => {
+            if Command::new("flatpak")
+                .args(["run", STEAM_FLATPAK_ID, &steam_url])
+                .spawn()
+                .is_err()
+            {
+                return Err(anyhow::anyhow!("Failed to launch Steam through Flatpak"));
+            }
+            return Ok(());
+        }
+        Some(SteamInstallKind::Snap) => {
+            if Command::new("snap")
+                .args(["run", "steam", &steam_url])
+                .spawn()
+                .is_err()
+            {
+                return Err(anyhow::anyhow!("Failed to launch Steam through Snap"));
+            }
+            return Ok(());
+        }
+        Some(SteamInstallKind::Native) | None => {}
+    }
+
+    let result = Command::new("xdg-open").arg(&steam_url).spawn();
 
     if let Err(_) = result {
-        if let Err(_) = Command::new("steam")
-            .args([format!("steam://run/{}", LIMBUS_STEAM_ID)])
-            .spawn()
-        {
+        if let Err(_) = Command::new("steam").arg(&steam_url).spawn() {
             return Err(anyhow::anyhow!("Failed to launch Steam. Is it installed?"));
         }
     }
@@ -36,6 +163,33 @@ pub fn launch_game() -> Result<(), Error> {
     Ok(())
 }
 
+/// Returns the Steam client program and the leading arguments needed to run it.
+#[cfg(target_os = "windows")]
+pub fn get_steam_command() -> Result<(PathBuf, Vec<String>), Error> {
+    let steam_path = get_steam_path()?;
+    Ok((steam_path.join("steam.exe"), vec![]))
+}
+
+/// Returns the Steam client program and the leading arguments needed to run it.
+#[cfg(target_os = "linux")]
+pub fn get_steam_command() -> Result<(PathBuf, Vec<String>), Error> {
+    let install_kind = dirs::home_dir()
+        .and_then(|home| find_steam_installation(&home))
+        .map(|(_, kind)| kind);
+
+    Ok(match install_kind {
+        Some(SteamInstallKind::Flatpak) => (
+            PathBuf::from("flatpak"),
+            vec!["run".to_string(), STEAM_FLATPAK_ID.to_string()],
+        ),
+        Some(SteamInstallKind::Snap) => (
+            PathBuf::from("snap"),
+            vec!["run".to_string(), "steam".to_string()],
+        ),
+        Some(SteamInstallKind::Native) | None => (PathBuf::from("steam"), vec![]),
+    })
+}
+
 #[cfg(target_os = "windows")]
 fn get_steam_path() -> io::Result<PathBuf> {
     use winreg::enums::*;
@@ -75,13 +229,8 @@ fn get_steam_path() -> io::Result<PathBuf> {
 fn get_steam_path() -> io::Result<PathBuf> {
     let home = dirs::home_dir()
         .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
-    let path = home.join(".steam/steam");
-    if path.exists() {
-        return Ok(path);
-    }
 
-    let path = home.join(".local/share/Steam");
-    if path.exists() {
+    if let Some((path, _)) = find_steam_installation(&home) {
         return Ok(path);
     }
 
@@ -106,58 +255,318 @@ pub fn validate_game_directory(directory: &str) -> Result<(), Error> {
     Ok(())
 }
 
-pub fn get_game_directory() -> Result<PathBuf, Error> {
-    let steam_path = get_steam_path()?;
-    let default_path = steam_path.join("steamapps").join("common");
+fn read_vdf_file(path: &Path) -> Result<vdf::VdfValue, Error> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read Steam file {:?}", path))?;
+
+    vdf::parse(&content).with_context(|| format!("Failed to parse Steam file {:?}", path))
+}
+
+/// Returns `steamapps` directories of all Steam libraries, the ones
+/// that list Limbus in their apps first.
+fn get_steam_libraries(steam_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let default_library = steam_path.join("steamapps");
+    let vdf_path = default_library.join("libraryfolders.vdf");
 
-    let vdf_path = steam_path.join("steamapps").join("libraryfolders.vdf");
     if !vdf_path.exists() {
-        return Err(anyhow::anyhow!("Steam libraries file not found"));
+        warn!("Steam libraries file not found at {:?}", vdf_path);
+        return Ok(vec![default_library]);
     }
 
-    let vdf_content = std::fs::read_to_string(vdf_path)
-        .with_context(|| format!("Failed to read Steam libraries file"))?;
+    let library_folders = read_vdf_file(&vdf_path)?;
+    let library_folders = library_folders
+        .get("libraryfolders")
+        .ok_or_else(|| anyhow::anyhow!("Invalid Steam libraries file"))?;
 
-    let mut library_paths = vec![default_path];
-    for line in vdf_content.lines() {
-        if line.contains("\"path\"") {
-            if let Some(path_str) = line.split('"').nth(3) {
-                let lib_path = PathBuf::from(path_str).join("steamapps").join("common");
-                library_paths.push(lib_path);
+    let app_id = LIMBUS_STEAM_ID.to_string();
+    let mut libraries = Vec::new();
+
+    for (key, value) in library_folders.entries() {
+        let (path, has_game) = match value {
+            vdf::VdfValue::Object(_) => {
+                let has_game = value
+                    .get("apps")
+                    .is_some_and(|apps| apps.get(&app_id).is_some());
+                (value.get_str("path"), has_game)
+            }
+            // Old format: "1" "D:\\SteamLibrary"
+            vdf::VdfValue::String(path) if key.parse::<u32>().is_ok() => {
+                (Some(path.as_str()), false)
             }
+            _ => continue,
+        };
+
+        if let Some(path) = path {
+            libraries.push((PathBuf::from(path).join("steamapps"), has_game));
         }
     }
 
-    for library in library_paths {
-        let parent = library
-            .parent()
-            .ok_or(anyhow::anyhow!("Invalid library path"))?;
-        let manifest_path = parent.join(format!("appmanifest_{}.acf", LIMBUS_STEAM_ID));
+    libraries.sort_by_key(|(_, has_game)| !has_game);
 
-        if manifest_path.exists() {
-            let manifest = std::fs::read_to_string(manifest_path)
-                .with_context(|| format!("Failed to read game manifest"))?;
+    let mut library_paths: Vec<PathBuf> = Vec::new();
+    for (library, _) in libraries.into_iter().chain([(default_library, false)]) {
+        if !library_paths.contains(&library) {
+            library_paths.push(library);
+        }
+    }
+
+    Ok(library_paths)
+}
 
-            for line in manifest.lines() {
-                if line.contains("\"installdir\"") {
-                    if let Some(dir_name) = line.split('"').nth(3) {
-                        return Ok(library.join(dir_name));
-                    }
-                }
+pub fn get_game_directory() -> Result<PathBuf, Error> {
+    let steam_path = get_steam_path()?;
+    find_game_in_libraries(&steam_path)
+}
+
+fn find_game_in_libraries(steam_path: &Path) -> Result<PathBuf, Error> {
+    for library in get_steam_libraries(steam_path)? {
+        let manifest_path = library.join(format!("appmanifest_{}.acf", LIMBUS_STEAM_ID));
+
+        if !manifest_path.exists() {
+            continue;
+        }
+
+        let manifest = match read_vdf_file(&manifest_path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Skipping game manifest: {:?}", e);
+                continue;
+            }
+        };
+        let install_dir = manifest
+            .get_path(&["AppState", "installdir"])
+            .and_then(|value| value.as_str());
+
+        match install_dir {
+            Some(dir_name) if !dir_name.is_empty() => {
+                return Ok(library.join("common").join(dir_name));
             }
+            _ => warn!("No installdir in game manifest {:?}", manifest_path),
         }
     }
 
     Err(anyhow::anyhow!("Limbus not found in any Steam library"))
 }
 
-pub fn is_game_running() -> bool {
-    let system = System::new_all();
+/// Reads build information from the Steam manifest next to the game directory.
+/// Returns `None` for installs that are not managed by a Steam library.
+pub fn get_game_build_info(game_path: &Path) -> Result<Option<GameBuildInfo>, Error> {
+    let library = match game_path.parent().and_then(|common| common.parent()) {
+        Some(library) => library,
+        None => return Ok(None),
+    };
 
-    system.processes().iter().any(|(_, process)| {
-        process
-            .name()
-            .to_str()
-            .map_or(false, |name| name.starts_with("LimbusCompany.e"))
-    })
+    let manifest_path = library.join(format!("appmanifest_{}.acf", LIMBUS_STEAM_ID));
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+
+    let manifest = read_vdf_file(&manifest_path)?;
+    GameBuildInfo::from_manifest(&manifest).map(Some)
+}
+
+pub fn refresh_processes(system: &mut System) {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing()
+            .with_exe(UpdateKind::OnlyIfNotSet)
+            .with_cmd(UpdateKind::OnlyIfNotSet),
+    );
+}
+
+fn is_game_process(process: &Process) -> bool {
+    let is_game_exe = |path: &str| path.to_lowercase().ends_with("limbuscompany.exe");
+
+    // Linux truncates process names to 15 characters, and under Proton/Wine
+    // the Windows executable may only show up in the exe path or command line
+    process
+        .name()
+        .to_str()
+        .is_some_and(|name| name.starts_with("LimbusCompany.e"))
+        || process
+            .exe()
+            .and_then(|exe| exe.to_str())
+            .is_some_and(is_game_exe)
+        || process
+            .cmd()
+            .iter()
+            .any(|arg| arg.to_str().is_some_and(is_game_exe))
+}
+
+pub fn has_game_process(system: &System) -> bool {
+    system.processes().values().any(is_game_process)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[cfg(target_os = "linux")]
+    fn find_in_fake_home(steam_dir: Option<&str>) -> Option<(PathBuf, SteamInstallKind)> {
+        let home = tempfile::tempdir().unwrap();
+        if let Some(steam_dir) = steam_dir {
+            fs::create_dir_all(home.path().join(steam_dir)).unwrap();
+        }
+
+        find_steam_installation(home.path())
+            .map(|(path, kind)| (path.strip_prefix(home.path()).unwrap().to_path_buf(), kind))
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_native_steam() {
+        assert_eq!(
+            find_in_fake_home(Some(".local/share/Steam")),
+            Some((
+                PathBuf::from(".local/share/Steam"),
+                SteamInstallKind::Native
+            ))
+        );
+        assert_eq!(
+            find_in_fake_home(Some(".steam/steam")),
+            Some((PathBuf::from(".steam/steam"), SteamInstallKind::Native))
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_flatpak_steam() {
+        assert_eq!(
+            find_in_fake_home(Some(".var/app/com.valvesoftware.Steam/.local/share/Steam")),
+            Some((
+                PathBuf::from(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+                SteamInstallKind::Flatpak
+            ))
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_snap_steam() {
+        assert_eq!(
+            find_in_fake_home(Some("snap/steam/common/.local/share/Steam")),
+            Some((
+                PathBuf::from("snap/steam/common/.local/share/Steam"),
+                SteamInstallKind::Snap
+            ))
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn prefers_native_steam() {
+        let home = tempfile::tempdir().unwrap();
+        fs::create_dir_all(home.path().join(".local/share/Steam")).unwrap();
+        fs::create_dir_all(
+            home.path()
+                .join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_steam_installation(home.path()).map(|(_, kind)| kind),
+            Some(SteamInstallKind::Native)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reports_missing_steam() {
+        assert_eq!(find_in_fake_home(None), None);
+        assert_eq!(find_in_fake_home(Some(".var/app/org.other.App")), None);
+    }
+
+    #[test]
+    fn reads_build_info_from_manifest() {
+        let manifest = vdf::parse(
+            r#""AppState" { "buildid" "14758931" "StateFlags" "1030" "LastUpdated" "1719291432" }"#,
+        )
+        .unwrap();
+        let game_build = GameBuildInfo::from_manifest(&manifest).unwrap();
+
+        assert_eq!(game_build.build_id, Some(14758931));
+        assert_eq!(game_build.last_updated, 1719291432);
+        assert!(game_build.update_pending);
+        assert!(game_build.update_in_progress);
+    }
+
+    #[test]
+    fn missing_build_id_is_unknown() {
+        let manifest = vdf::parse(r#""AppState" { "StateFlags" "4" }"#).unwrap();
+        assert_eq!(
+            GameBuildInfo::from_manifest(&manifest).unwrap().build_id,
+            None
+        );
+
+        let manifest = vdf::parse(r#""AppState" { "buildid" "" }"#).unwrap();
+        assert_eq!(
+            GameBuildInfo::from_manifest(&manifest).unwrap().build_id,
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_state_flags() {
+        let manifest = vdf::parse(r#""AppState" { "StateFlags" "4294967296" }"#).unwrap();
+        assert!(GameBuildInfo::from_manifest(&manifest).is_err());
+    }
+
+    fn write_manifest(library: &Path, content: &str) {
+        fs::create_dir_all(library).unwrap();
+        fs::write(
+            library.join(format!("appmanifest_{}.acf", LIMBUS_STEAM_ID)),
+            content,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn finds_game_in_secondary_library() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let steam_path = temp_dir.path().join("Steam");
+        let broken_library = temp_dir.path().join("Broken");
+        let library = temp_dir.path().join("SteamLibrary");
+
+        fs::create_dir_all(steam_path.join("steamapps")).unwrap();
+        fs::write(
+            steam_path.join("steamapps/libraryfolders.vdf"),
+            format!(
+                r#""libraryfolders"
+{{
+	"0" {{ "path" "{}" "apps" {{ }} }}
+	"1" {{ "path" "{}" "apps" {{ "1973530" "1" }} }}
+	"2" {{ "path" "{}" "apps" {{ "1973530" "1" }} }}
+}}"#,
+                steam_path.display(),
+                broken_library.display(),
+                library.display()
+            ),
+        )
+        .unwrap();
+
+        // An unreadable manifest in one library must not stop the search
+        write_manifest(&broken_library.join("steamapps"), r#""AppState" {"#);
+        write_manifest(
+            &library.join("steamapps"),
+            r#""AppState" { "appid" "1973530" "installdir" "Limbus Company" }"#,
+        );
+
+        let libraries = get_steam_libraries(&steam_path).unwrap();
+        assert_eq!(libraries.last(), Some(&steam_path.join("steamapps")));
+
+        assert_eq!(
+            find_game_in_libraries(&steam_path).unwrap(),
+            library.join("steamapps/common/Limbus Company")
+        );
+    }
+
+    #[test]
+    fn reports_missing_game() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("steamapps")).unwrap();
+
+        assert!(find_game_in_libraries(temp_dir.path()).is_err());
+    }
 }
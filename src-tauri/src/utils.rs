@@ -1,3 +1,5 @@
+use crate::archive_cache::{self, ArchiveCache};
+use crate::font_validation;
 use anyhow::Context;
 use futures::stream::StreamExt;
 use log::{debug, info, warn};
@@ -36,9 +38,24 @@ pub enum Format {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Font {
-    pub url: String,  // Url to font file
-    pub hash: String, // Md5 hash of the font file
-    pub name: String, // Filename in Font/ folder
+    pub url: Option<String>, // Url to font file, None if bundled in the archive's Font/ folder
+    pub hash: String,        // Md5 hash of the font file
+    pub name: String,        // Filename in Font/ folder
+    pub required_glyphs: Option<String>, // Characters the font must have glyphs for
+}
+
+impl Font {
+    /// Cached fonts are stored as <md5>.<ext>
+    pub fn get_cache_file_name(&self) -> String {
+        let extension = Path::new(self.url.as_deref().unwrap_or(&self.name))
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .filter(|ext| ext == "ttf" || ext == "otf")
+            .unwrap_or_else(|| "ttf".to_string());
+
+        format!("{}.{}", self.hash, extension)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -52,8 +69,39 @@ pub struct Localization {
     pub authors: Vec<String>, // List of authors
     pub url: String,          // Url to zip archive
     pub size: u64,            // Size of the zip archive to check integrity
+    pub hash: Option<String>, // Md5 hash of the zip archive, used as its cache key
     pub fonts: Vec<Font>,     // List of fonts to install
     pub format: Format,
+    pub min_build: Option<u64>, // Oldest supported Steam build id of the game
+    pub max_build: Option<u64>, // Newest supported Steam build id of the game
+}
+
+impl Localization {
+    pub fn is_compatible_with(&self, game_build: u64) -> bool {
+        self.min_build
+            .is_none_or(|min_build| game_build >= min_build)
+            && self
+                .max_build
+                .is_none_or(|max_build| game_build <= max_build)
+    }
+
+    pub fn check_compatibility(&self, game_build: Option<u64>) -> Result<(), anyhow::Error> {
+        match game_build {
+            Some(game_build) if !self.is_compatible_with(game_build) => Err(anyhow::anyhow!(
+                "Localization '{}' version '{}' does not support game build {}",
+                self.id,
+                self.version,
+                game_build
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InstalledFont {
+    pub name: String, // Filename in Font/ folder
+    pub hash: String, // Md5 hash of the font file
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -61,12 +109,19 @@ pub struct InstalledLocalization {
     pub id: String,
     pub version: String,
     pub source: String,
+    pub game_build: Option<u64>, // Steam build id of the game at install time
+    pub fonts: Option<Vec<InstalledFont>>, // None for entries installed by older versions
+    #[serde(default)]
+    pub record: Option<Localization>, // Source entry of the installed version
+    #[serde(default)]
+    pub previous: Option<Localization>, // Source entry of the version it replaced, for rollback
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct InstalledMetadata {
     pub format_version: u32,
     pub installed: HashMap<String, InstalledLocalization>,
+    pub previous_language: Option<String>, // Game language selected before the manager switched it
 }
 
 impl InstalledMetadata {
@@ -74,23 +129,122 @@ impl InstalledMetadata {
         Self {
             format_version: 1,
             installed: HashMap::new(),
+            previous_language: None,
         }
     }
 
-    pub fn with_localization(localization: &Localization, source: &String) -> Self {
-        let mut installed_metadata = Self::new();
+    pub fn add_localization(
+        &mut self,
+        localization: &Localization,
+        source: &str,
+        game_build: Option<u64>,
+    ) {
+        // Reinstalling the same version keeps the version to roll back to
+        let previous = match self.installed.get(&localization.id) {
+            Some(installed) if installed.version == localization.version => {
+                installed.previous.clone()
+            }
+            Some(installed) => installed.record.clone(),
+            None => None,
+        };
 
-        installed_metadata.installed.insert(
+        self.installed.insert(
             localization.id.clone(),
             InstalledLocalization {
                 id: localization.id.clone(),
                 version: localization.version.clone(),
-                source: source.clone(),
+                source: source.to_string(),
+                game_build,
+                fonts: Some(
+                    localization
+                        .fonts
+                        .iter()
+                        .map(|font| InstalledFont {
+                            name: font.name.clone(),
+                            hash: font.hash.clone(),
+                        })
+                        .collect(),
+                ),
+                record: Some(localization.clone()),
+                previous,
             },
         );
+    }
+}
+
+pub fn get_backup_path(path: &Path) -> PathBuf {
+    let mut backup_name = path.as_os_str().to_owned();
+    backup_name.push(".bak");
+    PathBuf::from(backup_name)
+}
+
+/// Replaces `path` with `content` through a synced temporary file, so a crash
+/// leaves either the old or the new version. The old version is kept as `<path>.bak`.
+pub fn write_file_atomic(path: &Path, content: &[u8]) -> Result<(), anyhow::Error> {
+    replace_file(path, content, true)
+}
 
-        installed_metadata
+/// Like `write_file_atomic`, but leaves `<path>.bak` alone. Used to put the
+/// backup back in place of a broken file without backing up the broken one.
+pub fn restore_file_atomic(path: &Path, content: &[u8]) -> Result<(), anyhow::Error> {
+    replace_file(path, content, false)
+}
+
+fn replace_file(path: &Path, content: &[u8], keep_backup: bool) -> Result<(), anyhow::Error> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file path {:?}", path))?;
+
+    let mut temp_file = Builder::new()
+        .prefix(".tmp")
+        .tempfile_in(parent)
+        .with_context(|| format!("Failed to create temporary file in {:?}", parent))?;
+    temp_file.write_all(content)?;
+    temp_file
+        .as_file()
+        .sync_all()
+        .with_context(|| format!("Failed to sync temporary file for {:?}", path))?;
+
+    if keep_backup && path.exists() {
+        let backup_path = get_backup_path(path);
+        fs::copy(path, &backup_path)
+            .with_context(|| format!("Failed to back up {:?} to {:?}", path, backup_path))?;
+    }
+
+    temp_file
+        .persist(path)
+        .with_context(|| format!("Failed to replace {:?}", path))?;
+
+    // The rename itself is only durable once the directory is synced
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(parent) {
+        let _ = dir.sync_all();
     }
+
+    Ok(())
+}
+
+fn read_installed_metadata(config_path: &Path) -> Result<InstalledMetadata, anyhow::Error> {
+    let config_content = fs::read_to_string(config_path)
+        .with_context(|| format!("Failed to read {:?}", config_path))?;
+    let metadata: InstalledMetadata = toml::from_str(&config_content)
+        .with_context(|| format!("Failed to parse {:?}", config_path))?;
+    Ok(metadata)
+}
+
+/// Whether `game_path` has its own metadata file, or at least a backup of it.
+pub fn has_installed_metadata(game_path: &Path) -> bool {
+    let config_path = game_path.join(METADATA_FILE_NAME);
+    config_path.exists() || get_backup_path(&config_path).exists()
+}
+
+/// Whether the files of an installed localization are still in the game directory.
+pub fn is_localization_present(game_path: &Path, localization_id: &str) -> bool {
+    game_path
+        .join("LimbusCompany_Data")
+        .join("Lang")
+        .join(localization_id)
+        .is_dir()
 }
 
 pub fn load_installed_metadata(game_path: &PathBuf) -> Result<InstalledMetadata, anyhow::Error> {
@@ -98,23 +252,60 @@ pub fn load_installed_metadata(game_path: &PathBuf) -> Result<InstalledMetadata,
 
     if !config_path.exists() {
         let metadata = InstalledMetadata::new();
-        let config_content = toml::to_string(&metadata)?;
-        fs::write(&config_path, config_content)?;
+        save_installed_metadata(game_path, &metadata)?;
         return Ok(metadata);
     }
 
-    let config_content = fs::read_to_string(&config_path)?;
-    let metadata: InstalledMetadata = toml::from_str(&config_content)?;
+    let error = match read_installed_metadata(&config_path) {
+        Ok(metadata) => return Ok(metadata),
+        Err(e) => e,
+    };
+
+    let backup_path = get_backup_path(&config_path);
+    if !backup_path.exists() {
+        return Err(error);
+    }
+
+    warn!("{:#}, restoring {:?}", error, backup_path);
+    let metadata = read_installed_metadata(&backup_path)?;
+
+    // Put the good copy back, so the next save doesn't back up the broken one
+    let config_content = toml::to_string(&metadata)?;
+    restore_file_atomic(&config_path, config_content.as_bytes())
+        .with_context(|| format!("Failed to restore {:?}", config_path))?;
+
     Ok(metadata)
 }
 
+/// Reads the metadata of `game_path` without creating or repairing anything.
+/// Returns None if the game directory has no metadata file.
+pub fn peek_installed_metadata(
+    game_path: &Path,
+) -> Result<Option<InstalledMetadata>, anyhow::Error> {
+    let config_path = game_path.join(METADATA_FILE_NAME);
+    let backup_path = get_backup_path(&config_path);
+
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    match read_installed_metadata(&config_path) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(e) if backup_path.exists() => {
+            warn!("{:#}, reading {:?}", e, backup_path);
+            read_installed_metadata(&backup_path).map(Some)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 pub fn save_installed_metadata(
     game_path: &PathBuf,
     metadata: &InstalledMetadata,
 ) -> Result<(), anyhow::Error> {
     let config_path = game_path.join(METADATA_FILE_NAME);
     let config_content = toml::to_string(metadata)?;
-    fs::write(&config_path, config_content)?;
+    write_file_atomic(&config_path, config_content.as_bytes())?;
     Ok(())
 }
 
@@ -141,27 +332,102 @@ pub async fn fetch_available_localizations(url: &str) -> Result<Vec<Localization
     Ok(localizations.localizations)
 }
 
+#[derive(Serialize, Clone, Debug)]
+pub struct SourceTestResult {
+    pub format_version: u32,
+    pub localizations: Vec<String>, // Ids of the localizations the source offers
+    pub warnings: Vec<String>,
+}
+
+/// Accepts only absolute http(s) URLs.
+pub fn validate_source_url(url: &str) -> Result<(), anyhow::Error> {
+    let parsed_url =
+        reqwest::Url::parse(url.trim()).with_context(|| format!("Invalid URL '{}'", url))?;
+
+    match parsed_url.scheme() {
+        "http" | "https" => {}
+        scheme => {
+            return Err(anyhow::anyhow!(
+                "Unsupported URL scheme '{}', expected http or https",
+                scheme
+            ))
+        }
+    }
+
+    if parsed_url.host_str().is_none_or(|host| host.is_empty()) {
+        return Err(anyhow::anyhow!("URL '{}' has no host", url));
+    }
+
+    Ok(())
+}
+
+/// Downloads the manifest of a source and checks that it is usable.
+pub async fn test_source(url: &str) -> Result<SourceTestResult, anyhow::Error> {
+    validate_source_url(url)?;
+
+    let client = Client::new();
+
+    let response = client
+        .get(url.trim())
+        .header("User-Agent", "Limbus Launcher")
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach source {}", url))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("HTTP error: {}", response.status()));
+    }
+
+    let body = response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read response from {}", url))?;
+
+    let available: AvailableLocalizations = serde_json::from_str(&body)
+        .with_context(|| format!("Source {} does not provide a valid localization list", url))?;
+
+    let mut warnings = Vec::new();
+    let mut ids: Vec<String> = Vec::new();
+
+    for localization in &available.localizations {
+        if ids.contains(&localization.id) {
+            warnings.push(format!("Duplicate localization id '{}'", localization.id));
+            continue;
+        }
+
+        if let Format::Unknown(format) = &localization.format {
+            warnings.push(format!(
+                "Localization '{}' uses unsupported format '{}'",
+                localization.id, format
+            ));
+        }
+
+        ids.push(localization.id.clone());
+    }
+
+    if ids.is_empty() {
+        warnings.push("Source does not offer any localizations".to_string());
+    }
+
+    Ok(SourceTestResult {
+        format_version: available.format_version,
+        localizations: ids,
+        warnings,
+    })
+}
+
 pub async fn install_fonts_for_localization(
     game_path: &PathBuf,
+    font_cache_dir: &Path,
     localization: &Localization,
 ) -> Result<(), anyhow::Error> {
-    let font_cache_dir = game_path.join("FontCache");
-    fs::create_dir_all(&font_cache_dir)
+    fs::create_dir_all(font_cache_dir)
         .with_context(|| format!("Failed to create FontCache directory"))?;
 
     for font_info in &localization.fonts {
-        let font_url = &font_info.url;
         let expected_hash = &font_info.hash;
-
-        let extension = Path::new(font_url)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_lowercase())
-            .filter(|ext| ext == "ttf" || ext == "otf")
-            .unwrap_or_else(|| "ttf".to_string());
-
-        let chache_font_filename = format!("{}.{}", expected_hash, extension);
-        let font_cache_path = font_cache_dir.join(&chache_font_filename);
+        let font_cache_path = font_cache_dir.join(font_info.get_cache_file_name());
 
         let mut needs_download = true;
         if font_cache_path.exists() {
@@ -169,6 +435,15 @@ pub async fn install_fonts_for_localization(
             match calculate_md5(&font_cache_path) {
                 Ok(calculated_hash) => {
                     if calculated_hash == *expected_hash {
+                        // Fonts cached before validation existed may not be fonts at all
+                        if let Err(e) = font_validation::validate_font_file(
+                            &font_cache_path,
+                            font_info.required_glyphs.as_deref(),
+                        ) {
+                            fs::remove_file(&font_cache_path).ok();
+                            return Err(e.context(format!("Font '{}' is invalid", font_info.name)));
+                        }
+
                         info!("Cached font hash matches. Skipping download.");
                         needs_download = false;
                     } else {
@@ -200,8 +475,22 @@ pub async fn install_fonts_for_localization(
         }
 
         if needs_download {
+            // Bundled fonts are put into the cache while the archive is installed
+            let font_url = font_info.url.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Bundled font '{}' was not found in the archive",
+                    font_info.name
+                )
+            })?;
+
             info!("Downloading font from: {}", font_url);
-            download_and_validate_font(font_url, &font_cache_path, expected_hash).await?;
+            download_and_validate_font(
+                font_url,
+                &font_cache_path,
+                expected_hash,
+                font_info.required_glyphs.as_deref(),
+            )
+            .await?;
         } else {
             info!("Using cached font: {:?}", font_cache_path);
         }
@@ -271,17 +560,42 @@ pub async fn install_fonts_for_localization(
 
 pub async fn install_localization(
     game_path: &PathBuf,
+    archive_cache: &ArchiveCache,
+    font_cache_dir: &Path,
     localization: &Localization,
 ) -> Result<(), anyhow::Error> {
     let temp_dir = create_temp_directory(&localization.id)?;
     let extract_path = temp_dir.path();
 
-    let download_path = download_localization_file(&localization, &temp_dir).await?;
+    let download_path = match archive_cache.get(localization) {
+        Some(cached_path) => {
+            info!(
+                "Using cached archive for '{}' version '{}'",
+                localization.id, localization.version
+            );
+            cached_path
+        }
+        None => {
+            let download_path = download_localization_file(&localization, &temp_dir).await?;
+
+            if archive_cache.is_enabled() {
+                archive_cache
+                    .insert(localization, &download_path)
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to cache archive: {:?}", e);
+                        download_path
+                    })
+            } else {
+                download_path
+            }
+        }
+    };
 
     info!("Extracting localization to: {:?}", extract_path);
     extract_zip_archive(&download_path, extract_path)?;
 
     let language_dir = find_language_directory(extract_path, &localization.format)?;
+    cache_bundled_fonts(&language_dir, font_cache_dir, localization)?;
 
     install_to_game_directory(&game_path, &language_dir, &localization)?;
 
@@ -383,13 +697,9 @@ async fn download_localization_file(
         .flush()
         .with_context(|| format!("Failed to flush file data"))?;
 
-    let size = fs::metadata(&download_path)
-        .with_context(|| format!("Failed to get file size"))?
-        .len();
+    drop(output_file);
 
-    if size != localization.size {
-        return Err(anyhow::anyhow!("File size mismatch"));
-    }
+    archive_cache::verify_archive(&download_path, localization)?;
 
     info!(
         "Successfully downloaded localization from: {}",
@@ -499,6 +809,70 @@ fn find_compatible_language_dir(extract_path: &Path) -> Result<PathBuf, anyhow::
     ))
 }
 
+/// Verifies fonts shipped in the archive's Font/ folder and adds them to the font cache,
+/// so they are installed the same way as downloaded ones.
+fn cache_bundled_fonts(
+    language_dir: &Path,
+    font_cache_dir: &Path,
+    localization: &Localization,
+) -> Result<(), anyhow::Error> {
+    for font_info in localization.fonts.iter().filter(|font| font.url.is_none()) {
+        let bundled_font_path = language_dir.join("Font").join(&font_info.name);
+
+        if !bundled_font_path.is_file() {
+            return Err(anyhow::anyhow!(
+                "Bundled font '{}' is missing from the archive",
+                font_info.name
+            ));
+        }
+
+        let calculated_hash = calculate_md5(&bundled_font_path)?;
+        if calculated_hash != font_info.hash {
+            return Err(anyhow::anyhow!(
+                "Bundled font '{}' hash mismatch. Expected: {}, Calculated: {}",
+                font_info.name,
+                font_info.hash,
+                calculated_hash
+            ));
+        }
+
+        font_validation::validate_font_file(
+            &bundled_font_path,
+            font_info.required_glyphs.as_deref(),
+        )
+        .with_context(|| format!("Bundled font '{}' is invalid", font_info.name))?;
+
+        let font_cache_path = font_cache_dir.join(font_info.get_cache_file_name());
+        if font_cache_path.exists() {
+            match calculate_md5(&font_cache_path) {
+                Ok(cached_hash) if cached_hash == font_info.hash => continue,
+                Ok(cached_hash) => warn!(
+                    "Cached font hash mismatch (expected: {}, found: {}). Replacing {:?}",
+                    font_info.hash, cached_hash, font_cache_path
+                ),
+                Err(e) => warn!(
+                    "Failed to hash cached font {:?}: {}. Replacing it.",
+                    font_cache_path, e
+                ),
+            }
+        }
+
+        fs::create_dir_all(font_cache_dir).with_context(|| {
+            format!("Failed to create FontCache directory {:?}", font_cache_dir)
+        })?;
+        fs::copy(&bundled_font_path, &font_cache_path).with_context(|| {
+            format!(
+                "Failed to copy bundled font {:?} to cache {:?}",
+                bundled_font_path, font_cache_path
+            )
+        })?;
+
+        debug!("Cached bundled font {:?}", font_cache_path);
+    }
+
+    Ok(())
+}
+
 fn install_to_game_directory(
     game_path: &PathBuf,
     language_dir: &Path,
@@ -554,7 +928,7 @@ fn copy_directory_contents(src_dir: &Path, dest_dir: &Path) -> Result<(), anyhow
     Ok(())
 }
 
-fn calculate_md5(file_path: &Path) -> Result<String, anyhow::Error> {
+pub fn calculate_md5(file_path: &Path) -> Result<String, anyhow::Error> {
     let file = fs::File::open(file_path)
         .with_context(|| format!("Failed to open file for hashing {:?}", file_path))?;
 
@@ -580,6 +954,7 @@ async fn download_and_validate_font(
     url: &str,
     save_path: &Path,
     expected_hash: &str,
+    required_glyphs: Option<&str>,
 ) -> Result<(), anyhow::Error> {
     let client = Client::new();
 
@@ -636,6 +1011,12 @@ async fn download_and_validate_font(
             url, expected_hash, calculated_hash, temp_save_path
         ))
     } else {
+        // A matching hash only proves we got the file that was published
+        if let Err(e) = font_validation::validate_font_file(&temp_save_path, required_glyphs) {
+            fs::remove_file(&temp_save_path).ok();
+            return Err(e.context(format!("Font downloaded from {} is invalid", url)));
+        }
+
         fs::rename(&temp_save_path, save_path).with_context(|| {
             format!(
                 "Failed to rename temporary font file {:?} to {:?}",
@@ -650,3 +1031,221 @@ async fn download_and_validate_font(
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn test_localization(version: &str, font_names: &[&str]) -> Localization {
+        Localization {
+            id: "ru".to_string(),
+            version: version.to_string(),
+            name: "Русский".to_string(),
+            flag: "ru".to_string(),
+            icon: String::new(),
+            description: String::new(),
+            authors: vec![],
+            url: "https://example.com/ru.zip".to_string(),
+            size: 0,
+            hash: None,
+            fonts: font_names
+                .iter()
+                .map(|name| Font {
+                    url: Some(format!("https://example.com/{}", name)),
+                    hash: "0123456789abcdef0123456789abcdef".to_string(),
+                    name: name.to_string(),
+                    required_glyphs: None,
+                })
+                .collect(),
+            format: Format::New,
+            min_build: None,
+            max_build: None,
+        }
+    }
+
+    fn write_language_dir(dir: &Path, files: &[&str]) {
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file.as_bytes()).unwrap();
+        }
+    }
+
+    /// Smallest font the validator accepts: a table directory with empty tables.
+    fn minimal_font(variant: u8) -> Vec<u8> {
+        let mut font = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, variant,
+        ];
+        for tag in [b"cmap", b"head", b"maxp"] {
+            font.extend_from_slice(tag);
+            font.extend_from_slice(&[0; 4]);
+            font.extend_from_slice(&60u32.to_be_bytes());
+            font.extend_from_slice(&[0; 4]);
+        }
+        font
+    }
+
+    #[test]
+    fn bundled_fonts_replace_stale_cache_entries() {
+        let language_dir = tempfile::tempdir().unwrap();
+        let font_cache_dir = tempfile::tempdir().unwrap();
+
+        let font_path = language_dir.path().join("Font").join("Bundled.ttf");
+        fs::create_dir_all(font_path.parent().unwrap()).unwrap();
+        fs::write(&font_path, minimal_font(1)).unwrap();
+
+        let mut localization = test_localization("1", &["Bundled.ttf"]);
+        localization.fonts[0].url = None;
+        localization.fonts[0].hash = calculate_md5(&font_path).unwrap();
+
+        let font_cache_path = font_cache_dir
+            .path()
+            .join(localization.fonts[0].get_cache_file_name());
+        fs::write(&font_cache_path, minimal_font(2)).unwrap();
+
+        cache_bundled_fonts(language_dir.path(), font_cache_dir.path(), &localization).unwrap();
+        assert_eq!(fs::read(&font_cache_path).unwrap(), minimal_font(1));
+
+        // Entries that match are left alone
+        cache_bundled_fonts(language_dir.path(), font_cache_dir.path(), &localization).unwrap();
+        assert_eq!(fs::read(&font_cache_path).unwrap(), minimal_font(1));
+    }
+
+    #[test]
+    fn update_removes_renamed_fonts() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = game_dir.path().to_path_buf();
+        let fonts_dir = game_path.join("LimbusCompany_Data/Lang/ru/Font");
+
+        let old_archive = tempfile::tempdir().unwrap();
+        write_language_dir(
+            old_archive.path(),
+            &["StoryData/1.json", "Font/Bundled.ttf"],
+        );
+        install_to_game_directory(
+            &game_path,
+            old_archive.path(),
+            &test_localization("1", &["Old.ttf"]),
+        )
+        .unwrap();
+        // Downloaded fonts are copied in from the font cache after the archive
+        fs::write(fonts_dir.join("Old.ttf"), b"old font").unwrap();
+
+        let new_archive = tempfile::tempdir().unwrap();
+        write_language_dir(
+            new_archive.path(),
+            &["StoryData/1.json", "Font/Renamed.ttf"],
+        );
+        install_to_game_directory(
+            &game_path,
+            new_archive.path(),
+            &test_localization("2", &["New.ttf"]),
+        )
+        .unwrap();
+
+        assert!(!fonts_dir.join("Old.ttf").exists());
+        assert!(!fonts_dir.join("Bundled.ttf").exists());
+        assert!(fonts_dir.join("Renamed.ttf").is_file());
+        assert!(game_path
+            .join("LimbusCompany_Data/Lang/ru/StoryData/1.json")
+            .is_file());
+    }
+
+    #[test]
+    fn peek_does_not_create_metadata() {
+        let game_dir = tempfile::tempdir().unwrap();
+
+        assert!(peek_installed_metadata(game_dir.path()).unwrap().is_none());
+        assert!(!has_installed_metadata(game_dir.path()));
+    }
+
+    #[test]
+    fn peek_reads_backup_without_repairing() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = game_dir.path().to_path_buf();
+        let config_path = game_path.join(METADATA_FILE_NAME);
+
+        save_installed_metadata(&game_path, &InstalledMetadata::new()).unwrap();
+        save_installed_metadata(&game_path, &InstalledMetadata::new()).unwrap();
+        fs::write(&config_path, "installed = [").unwrap();
+
+        let metadata = peek_installed_metadata(&game_path).unwrap().unwrap();
+        assert!(metadata.installed.is_empty());
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "installed = [");
+    }
+
+    #[test]
+    fn peek_reports_unreadable_metadata() {
+        let game_dir = tempfile::tempdir().unwrap();
+        fs::write(game_dir.path().join(METADATA_FILE_NAME), "installed = [").unwrap();
+
+        assert!(peek_installed_metadata(game_dir.path()).is_err());
+    }
+
+    #[test]
+    fn update_keeps_the_replaced_version_for_rollback() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = game_dir.path().to_path_buf();
+
+        let mut metadata = InstalledMetadata::new();
+        metadata.add_localization(&test_localization("1.0", &["a.ttf"]), "main", Some(1));
+        assert!(metadata.installed["ru"].previous.is_none());
+
+        metadata.add_localization(&test_localization("2.0", &["a.ttf"]), "main", Some(2));
+        // Repairing the new version must not lose the rollback target
+        metadata.add_localization(&test_localization("2.0", &["a.ttf"]), "main", Some(2));
+
+        save_installed_metadata(&game_path, &metadata).unwrap();
+        let metadata = load_installed_metadata(&game_path).unwrap();
+
+        let installed = &metadata.installed["ru"];
+        assert_eq!(installed.version, "2.0");
+        assert_eq!(installed.record.as_ref().unwrap().version, "2.0");
+        let previous = installed.previous.as_ref().unwrap();
+        assert_eq!(previous.version, "1.0");
+        assert_eq!(previous.fonts[0].name, "a.ttf");
+    }
+
+    #[test]
+    fn atomic_write_backs_up_the_previous_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let backup_path = get_backup_path(&path);
+
+        write_file_atomic(&path, b"first").unwrap();
+        assert!(!backup_path.exists());
+
+        write_file_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read(&backup_path).unwrap(), b"first");
+
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn corrupt_metadata_is_restored_from_backup() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let game_path = game_dir.path().to_path_buf();
+        let config_path = game_path.join(METADATA_FILE_NAME);
+        let backup_path = get_backup_path(&config_path);
+
+        let mut metadata = InstalledMetadata::new();
+        metadata.add_localization(&test_localization("1.0", &[]), "main", None);
+        save_installed_metadata(&game_path, &metadata).unwrap();
+        save_installed_metadata(&game_path, &metadata).unwrap();
+        let backup_content = fs::read(&backup_path).unwrap();
+
+        fs::write(&config_path, "installed = [").unwrap();
+
+        let restored = load_installed_metadata(&game_path).unwrap();
+        assert_eq!(restored.installed["ru"].version, "1.0");
+
+        // The primary is readable again and the good backup is kept
+        assert_eq!(
+            read_installed_metadata(&config_path).unwrap().installed["ru"].version,
+            "1.0"
+        );
+        assert_eq!(fs::read(&backup_path).unwrap(), backup_content);
+    }
+}
@@ -0,0 +1,333 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VdfValue {
+    String(String),
+    Object(Vec<(String, VdfValue)>),
+}
+
+impl VdfValue {
+    /// Looks up a direct child by key. Keys are case-insensitive, as in Steam.
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::Object(entries) => entries
+                .iter()
+                .find(|(entry_key, _)| entry_key.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+            VdfValue::String(_) => None,
+        }
+    }
+
+    /// Follows a path of keys, e.g. `["AppState", "installdir"]`.
+    pub fn get_path(&self, path: &[&str]) -> Option<&VdfValue> {
+        path.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|value| value.as_str())
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(value) => Some(value),
+            VdfValue::Object(_) => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, VdfValue)] {
+        match self {
+            VdfValue::Object(entries) => entries,
+            VdfValue::String(_) => &[],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    String(String),
+    Open,
+    Close,
+}
+
+struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            chars: content.trim_start_matches('\u{feff}').chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow::anyhow!("VDF parse error on line {}: {}", self.line, message)
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, anyhow::Error> {
+        loop {
+            self.skip_whitespace();
+
+            match self.chars.peek() {
+                None => return Ok(None),
+                Some('/') => {
+                    self.chars.next();
+                    if self.chars.peek() != Some(&'/') {
+                        return Err(self.error("unexpected '/'"));
+                    }
+                    self.skip_line();
+                }
+                // Platform conditionals such as [$WIN32] are ignored
+                Some('[') => {
+                    for c in self.chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                Some('{') => {
+                    self.chars.next();
+                    return Ok(Some(Token::Open));
+                }
+                Some('}') => {
+                    self.chars.next();
+                    return Ok(Some(Token::Close));
+                }
+                Some('"') => {
+                    self.chars.next();
+                    return self.quoted_string().map(|value| Some(Token::String(value)));
+                }
+                Some(_) => return Ok(Some(Token::String(self.unquoted_string()))),
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.chars.next();
+        }
+    }
+
+    fn skip_line(&mut self) {
+        for c in self.chars.by_ref() {
+            if c == '\n' {
+                self.line += 1;
+                break;
+            }
+        }
+    }
+
+    fn quoted_string(&mut self) -> Result<String, anyhow::Error> {
+        let mut value = String::new();
+
+        loop {
+            match self.chars.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('\\') => value.push('\\'),
+                    Some('"') => value.push('"'),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    fn unquoted_string(&mut self) -> String {
+        let mut value = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                break;
+            }
+            value.push(c);
+            self.chars.next();
+        }
+
+        value
+    }
+}
+
+/// Parses a text KeyValues document such as `libraryfolders.vdf` or an `.acf` manifest.
+/// The result is an object holding the top-level keys, usually a single one like `"AppState"`.
+pub fn parse(content: &str) -> Result<VdfValue, anyhow::Error> {
+    let mut tokenizer = Tokenizer::new(content);
+    let entries = parse_entries(&mut tokenizer, false)?;
+    Ok(VdfValue::Object(entries))
+}
+
+fn parse_entries(
+    tokenizer: &mut Tokenizer,
+    nested: bool,
+) -> Result<Vec<(String, VdfValue)>, anyhow::Error> {
+    let mut entries = Vec::new();
+
+    loop {
+        let key = match tokenizer.next_token()? {
+            None if nested => return Err(tokenizer.error("missing '}'")),
+            None => return Ok(entries),
+            Some(Token::Close) if nested => return Ok(entries),
+            Some(Token::Close) => return Err(tokenizer.error("unexpected '}'")),
+            Some(Token::Open) => return Err(tokenizer.error("expected key, found '{'")),
+            Some(Token::String(key)) => key,
+        };
+
+        let value = match tokenizer.next_token()? {
+            None => return Err(tokenizer.error(&format!("missing value for key '{}'", key))),
+            Some(Token::Close) => {
+                return Err(tokenizer.error(&format!("missing value for key '{}'", key)))
+            }
+            Some(Token::Open) => VdfValue::Object(parse_entries(tokenizer, true)?),
+            Some(Token::String(value)) => VdfValue::String(value),
+        };
+
+        entries.push((key, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY_FOLDERS: &str = r#""libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"label"		""
+		"contentid"		"6034327145834588137"
+		"apps"
+		{
+			"228980"		"508193524"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"label"		"Games \"SSD\""
+		"apps"
+		{
+			"1973530"		"12163284992"
+		}
+	}
+}
+"#;
+
+    const APP_MANIFEST: &str = r#"// Written by Steam
+"AppState"
+{
+	"appid"		"1973530"
+	"name"		"Limbus Company"
+	"StateFlags"		"4" // fully installed
+	"installdir"		"Limbus Company"
+	"LastUpdated"		"1719291432"
+	"buildid"		"14758931"
+	"UserConfig"
+	{
+		"language"		"english"
+	}
+}
+"#;
+
+    #[test]
+    fn parses_nested_sections() {
+        let root = parse(LIBRARY_FOLDERS).unwrap();
+        let folders = root.get("libraryfolders").unwrap();
+
+        assert_eq!(folders.entries().len(), 2);
+        assert_eq!(
+            folders
+                .get_path(&["1", "apps", "1973530"])
+                .and_then(|v| v.as_str()),
+            Some("12163284992")
+        );
+        assert_eq!(
+            folders.get_path(&["0", "path"]).and_then(|v| v.as_str()),
+            Some("C:\\Program Files (x86)\\Steam")
+        );
+    }
+
+    #[test]
+    fn unescapes_quotes_and_backslashes() {
+        let root = parse(LIBRARY_FOLDERS).unwrap();
+
+        assert_eq!(
+            root.get_path(&["libraryfolders", "1", "label"])
+                .and_then(|v| v.as_str()),
+            Some("Games \"SSD\"")
+        );
+        assert_eq!(
+            root.get_path(&["libraryfolders", "1", "path"])
+                .and_then(|v| v.as_str()),
+            Some("D:\\SteamLibrary")
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        let root = parse(APP_MANIFEST).unwrap();
+        let app_state = root.get("AppState").unwrap();
+
+        assert_eq!(app_state.get_str("StateFlags"), Some("4"));
+        assert_eq!(app_state.get_str("installdir"), Some("Limbus Company"));
+        assert_eq!(
+            app_state
+                .get_path(&["UserConfig", "language"])
+                .and_then(|v| v.as_str()),
+            Some("english")
+        );
+    }
+
+    #[test]
+    fn keys_are_case_insensitive() {
+        let root = parse(APP_MANIFEST).unwrap();
+        assert_eq!(
+            root.get_path(&["appstate", "BUILDID"])
+                .and_then(|v| v.as_str()),
+            Some("14758931")
+        );
+    }
+
+    #[test]
+    fn finds_library_with_game() {
+        let root = parse(LIBRARY_FOLDERS).unwrap();
+        let library = root
+            .get("libraryfolders")
+            .unwrap()
+            .entries()
+            .iter()
+            .find(|(_, library)| library.get_path(&["apps", "1973530"]).is_some())
+            .and_then(|(_, library)| library.get_str("path"));
+
+        assert_eq!(library, Some("D:\\SteamLibrary"));
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        assert!(parse(r#""AppState" { "appid" "1""#).is_err());
+        assert!(parse(r#""AppState" { "appid" }"#).is_err());
+        assert!(parse(r#""AppState" "unterminated"#).is_err());
+        assert!(parse("}").is_err());
+    }
+}
@@ -2,7 +2,7 @@ import { rootStore } from "@/stores";
 import { Localization, Status } from "@/stores/models";
 import { observer } from "mobx-react-lite";
 import styles from "./actions.module.css";
-import { Hammer, Plus, X } from "lucide-react";
+import { Hammer, Plus, Undo2, X } from "lucide-react";
 import { useTranslation } from "react-i18next";
 import { Grid } from "react-loader-spinner";
 
@@ -15,6 +15,8 @@ function Actions({ localization }: ActionsProps) {
   const { t } = useTranslation();
 
   const installedVersion = state.installed?.[localization.id]?.version;
+  const previousVersion =
+    state.installed?.[localization.id]?.previous?.version;
   const status = actions.getStatus(localization);
   const isIdle = status === Status.Idle && !actions.startingGame;
 
@@ -34,6 +36,16 @@ function Actions({ localization }: ActionsProps) {
                 <button onClick={handleRepair} title={t("localization.repair")}>
                   <Hammer className="w-6 h-6 shrink-0" />
                 </button>
+                {previousVersion && (
+                  <button
+                    onClick={handleRollback}
+                    title={t("localization.rollback", {
+                      version: previousVersion,
+                    })}
+                  >
+                    <Undo2 className="w-6 h-6 shrink-0" />
+                  </button>
+                )}
                 <button
                   onClick={handleUninstall}
                   title={t("localization.uninstall")}
@@ -79,6 +91,10 @@ function Actions({ localization }: ActionsProps) {
   function handleRepair() {
     actions.repair(localization);
   }
+
+  function handleRollback() {
+    actions.rollback(localization);
+  }
 }
 
 export default observer(Actions);
@@ -144,6 +144,32 @@ export class ActionsStore {
     }
   }
 
+  public async rollback(localization: Localization) {
+    const status = this.status[localization.id] ?? Status.Idle;
+
+    if (status !== Status.Idle) {
+      throw new Error("Has operation in progress");
+    }
+
+    this.status[localization.id] = Status.RollingBack;
+
+    try {
+      await invoke("rollback_localization", {
+        localizationId: localization.id,
+      });
+      toastSuccess(
+        i18n.t("localization.rolledBack", { localization: localization.name })
+      );
+    } catch (error) {
+      toastError(i18n.t("error.rollback"));
+      console.error(error);
+    } finally {
+      runInAction(() => {
+        this.status[localization.id] = Status.Idle;
+      });
+    }
+  }
+
   public async updateAndPlay() {
     if (this.startingGame) {
       throw new Error("Game is already starting");
@@ -3,26 +3,87 @@ export interface LocalizationSource {
   url: string;
 }
 
+export interface GameProfile {
+  game_directory: string | null;
+  selected_source: string | null;
+  game_language: string | null;
+}
+
+export const LaunchMode = {
+  SteamUrl: "steam_url",
+  SteamApplaunch: "steam_applaunch",
+  Direct: "direct",
+  Custom: "custom",
+} as const;
+
+export type LaunchMode = (typeof LaunchMode)[keyof typeof LaunchMode];
+
+export interface LaunchSettings {
+  mode: LaunchMode;
+  extra_args: string[];
+  wrapper_command: string | null;
+  command_template: string | null;
+}
+
+export interface Hook {
+  name: string;
+  command: string;
+  timeout_secs: number;
+  continue_on_error: boolean;
+}
+
+export interface HookSettings {
+  pre_launch: Hook[];
+  post_exit: Hook[];
+}
+
 export interface AppSettings {
+  config_version: number;
   sources: Record<string, LocalizationSource>;
   selected_source: string | null;
   game_directory: string | null;
   language: string | null;
+  profiles: Record<string, GameProfile>;
+  active_profile: string | null;
+  launch: LaunchSettings;
+  hooks: HookSettings;
+  font_cache_directory: string | null;
+  archive_cache_max_size: number;
+  known_default_sources: Record<string, LocalizationSource>;
+}
+
+export interface InstalledFont {
+  name: string;
+  hash: string;
 }
 
 export interface InstalledLocalization {
   id: string;
   version: string;
   source: string;
+  game_build: number | null;
+  fonts: InstalledFont[] | null;
+  record: Localization | null;
+  previous: Localization | null;
 }
 
 export interface InstalledMetadata {
   installed: Record<string, InstalledLocalization>;
+  previous_language: string | null;
+}
+
+export interface GameBuildInfo {
+  build_id: number | null;
+  state_flags: number;
+  last_updated: number;
+  update_pending: boolean;
+  update_in_progress: boolean;
 }
 
 export interface AppState {
   settings: AppSettings;
   installed_metadata: InstalledMetadata | null;
+  game_build: GameBuildInfo | null;
 }
 
 export interface AvailableLocalizations {
@@ -37,9 +98,10 @@ export const Format = {
 export type Format = (typeof Format)[keyof typeof Format];
 
 export interface Font {
-  url: string;
+  url: string | null;
   hash: string;
   name: string;
+  required_glyphs?: string | null;
 }
 
 export interface Localization {
@@ -53,6 +115,117 @@ export interface Localization {
   url: string;
   fonts: Font[];
   format: Format;
+  min_build?: number | null;
+  max_build?: number | null;
+  hash?: string | null;
+}
+
+export type Operation =
+  | {
+      type: "install";
+      profile: string;
+      source: string;
+      localization: Localization;
+      select: boolean;
+      force: boolean;
+    }
+  | {
+      type: "repair";
+      profile: string;
+      source: string;
+      localization: Localization;
+    }
+  | {
+      type: "uninstall";
+      profile: string;
+      localization: Localization;
+    }
+  | {
+      type: "rollback";
+      profile: string;
+      localization_id: string;
+      force: boolean;
+    }
+  | {
+      type: "set_language";
+      profile: string;
+      language: string | null;
+    };
+
+export const OperationStatus = {
+  Pending: "pending",
+  Running: "running",
+  Failed: "failed",
+} as const;
+
+export type OperationStatus =
+  (typeof OperationStatus)[keyof typeof OperationStatus];
+
+export interface QueuedOperation {
+  id: number;
+  operation: Operation;
+  status: OperationStatus;
+  error: string | null;
+  queued_at: number;
+}
+
+export interface CachedFont {
+  file_name: string;
+  hash: string;
+  size: number;
+  referenced_by: string[];
+}
+
+export interface FontCacheReport {
+  path: string;
+  fonts: CachedFont[];
+  total_size: number;
+  unreferenced_size: number;
+}
+
+export interface PruneResult {
+  removed: string[];
+  freed_size: number;
+}
+
+export const AuditAction = {
+  Install: "install",
+  Update: "update",
+  Uninstall: "uninstall",
+  Repair: "repair",
+  Rollback: "rollback",
+} as const;
+
+export type AuditAction = (typeof AuditAction)[keyof typeof AuditAction];
+
+export interface AuditEvent {
+  timestamp: number;
+  action: AuditAction;
+  profile: string | null;
+  localization: string;
+  old_version: string | null;
+  new_version: string | null;
+  source: string | null;
+  game_build: number | null;
+  duration_ms: number;
+  error: string | null;
+}
+
+export interface AuditFilter {
+  localization?: string | null;
+  profile?: string | null;
+  source?: string | null;
+  actions?: AuditAction[];
+  succeeded?: boolean | null;
+  since?: number | null;
+  until?: number | null;
+  limit?: number | null;
+}
+
+export interface SourceTestResult {
+  format_version: number;
+  localizations: string[];
+  warnings: string[];
 }
 
 export interface RemoteLocalizations {
@@ -66,6 +239,7 @@ export const Status = {
   Uninstalling: "uninstalling",
   Updating: "updating",
   Repairing: "repairing",
+  RollingBack: "rolling_back",
 } as const;
 
 export type Status = (typeof Status)[keyof typeof Status];
@@ -96,3 +270,16 @@ export type Progress =
   | {
       type: "finished";
     };
+
+export type ConflictPolicy = "keep" | "overwrite" | "rename";
+
+export interface ImportReport {
+  added_sources: string[];
+  updated_sources: string[];
+  kept_sources: string[];
+  renamed_sources: [string, string][];
+  rejected_sources: [string, string][];
+  queued: string[];
+  up_to_date: string[];
+  missing: string[];
+}